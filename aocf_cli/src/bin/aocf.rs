@@ -1,26 +1,56 @@
 use aocf::{
     Aoc,
-    cookie::get_session_cookie,
+    account::{self, Account},
+    calendar,
+    events,
+    cookie::{get_chromium_session_cookie, get_session_cookie, get_session_cookie_expiry},
     find_root,
     Level,
+    SubmitOutcome,
+    leaderboard::{self, Leaderboard},
+    profile,
+    secret::{self, Storage},
+    release::{current_puzzle, event_days, next_release},
 };
 use aocf_cli::{
-    cli::{Aocf, AocfTimeDateOpts, generate_completion},
+    cli::{Aocf, AocfTimeDateOpts, Browser, Command, CookieStorage, generate_completion},
     conf::Conf,
+    exec::{format_elapsed, run_solver},
+    firefox,
+    leaderboard::{
+        render, render_global, render_global_markdown, render_markdown,
+        render_splits, render_splits_csv, render_splits_markdown,
+    },
     pretty::make_pretty,
 };
-use dirs::home_dir;
+use dirs::config_dir;
+use std::collections::{BTreeMap, hash_map::RandomState};
 use std::env;
 use std::fs;
-use std::io::Write;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process::{self, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 use tempfile::tempdir;
-use glob::glob;
-use failure::{Error, bail, format_err};
+use anyhow::{Error, bail, format_err};
 use regex::Regex;
 use clap::Parser;
-use chrono::{Utc, Datelike};
-use webbrowser;
+use chrono::{DateTime, Utc};
+
+/// Files in `.aocf` which shouldn't be committed, the cookie however it's
+/// stored, and state which changes with every request
+const GITIGNORE: &[&str] = &[
+    "cookie", "cookie.enc", "cookie.keyring", "last_request", "last_request.lock",
+];
+
+/// Cookies read for each profile
+static COOKIES: Mutex<BTreeMap<Option<String>, String>> = Mutex::new(BTreeMap::new());
+
+/// Days before a session cookie expires to start warning about it
+const EXPIRY_WARNING_DAYS: i64 = 7;
 
 fn main() {
     let opt = Aocf::parse();
@@ -42,11 +72,18 @@ fn write_conf(conf: &Conf) -> Result<(), Error> {
 }
 
 fn run(args: &Aocf) -> Result<(), Error> {
-    match args {
-        Aocf::Init => return init(),
-        Aocf::SetCookie { token } => return set_cookie(token),
-        Aocf::GetCookie => return get_cookie(),
-        Aocf::Completion { shell } => return {
+    let profile = args.profile.as_deref();
+
+    match &args.command {
+        Command::Init => return init(),
+        Command::SetCookie { token, storage } => return set_cookie(token, profile, *storage),
+        Command::GetCookie { list_profiles: true, browser: Browser::Firefox, .. } => return list_profiles(),
+        Command::GetCookie { list_profiles: true, .. } => bail!("listing profiles is only supported for Firefox"),
+        Command::GetCookie { browser, store, browser_profile, keyring_secret, storage, .. } => {
+            let store = store.as_deref();
+            return get_cookie(*browser, store, browser_profile.as_deref(), keyring_secret.as_deref(), profile, *storage)
+        },
+        Command::Completion { shell } => return {
             generate_completion(*shell);
             Ok(())
         },
@@ -58,30 +95,32 @@ fn run(args: &Aocf) -> Result<(), Error> {
 
     let conf_hash = conf.calc_hash();
 
+    // repositories set up before state was kept in .aocf may not ignore it
+    write_gitignore()?;
+
     // Check that the cookie is in place
-    let cookie_path = find_root()?.join(".aocf/cookie");
-    if !cookie_path.exists() {
-        bail!("cookie not found, please run set-cookie or get-cookie");
+    if Storage::detect(profile_dir(profile)?).is_none() {
+        match profile {
+            Some(p) => bail!("cookie not found for profile {}, please run set-cookie or get-cookie with --profile {}", p, p),
+            None => bail!("cookie not found, please run set-cookie or get-cookie"),
+        }
     }
 
-    let mut aoc = Aoc::new()
-        .parse_cli(false)
+    let mut aoc = new_aoc(&conf, profile)?
         .year(Some(conf.year))
         .day(Some(conf.day))
         .init()?;
 
-    match args {
-        Aocf::Fetch { force, now, day } => {
+    match &args.command {
+        Command::Fetch { force, now, day } => {
             aoc = if *now {
-                let now = Utc::now();
-                Aoc::new()
-                    .parse_cli(false)
-                    .year(Some(now.year()))
-                    .day(Some(now.day()))
+                let (year, day) = today()?;
+                new_aoc(&conf, profile)?
+                    .year(Some(year))
+                    .day(Some(day))
                     .init()?
             } else if let Some(d) = day {
-                Aoc::new()
-                    .parse_cli(false)
+                new_aoc(&conf, profile)?
                     .year(aoc.year)
                     .day(Some(*d))
                     .init()?
@@ -93,17 +132,15 @@ fn run(args: &Aocf) -> Result<(), Error> {
             let _ = aoc.get_input(*force)?;
             aoc.write()?;
         },
-        Aocf::Brief { pretty, view, force, now, day, web } => {
+        Command::Brief { pretty, view, force, now, day, web } => {
             aoc = if *now {
-                let now = Utc::now();
-                Aoc::new()
-                    .parse_cli(false)
-                    .year(Some(now.year()))
-                    .day(Some(now.day()))
+                let (year, day) = today()?;
+                new_aoc(&conf, profile)?
+                    .year(Some(year))
+                    .day(Some(day))
                     .init()?
             } else if let Some(d) = day {
-                Aoc::new()
-                    .parse_cli(false)
+                new_aoc(&conf, profile)?
                     .year(aoc.year)
                     .day(Some(*d))
                     .init()?
@@ -125,7 +162,7 @@ fn run(args: &Aocf) -> Result<(), Error> {
                 display(*pretty, *view, &conf, &brief)?
             }
         },
-        Aocf::Input { view, force, info } => {
+        Command::Input { view, force, info } => {
             let input = aoc.get_input(*force)?;
             aoc.write()?;
             if *info {
@@ -134,14 +171,109 @@ fn run(args: &Aocf) -> Result<(), Error> {
                 display(false, *view, &conf, &input)?
             }
         },
-        Aocf::Submit { answer } => {
-            println!("{}", aoc.submit(answer)?);
+        Command::Submit { answer, wait } => {
+            // don't wait out the cooldown for an answer which will be refused
+            aoc.check_answer(answer)?;
+            let outcome = loop {
+                if let (true, Some(remaining)) = (*wait, aoc.cooldown_remaining()) {
+                    eprintln!("waiting {} for cooldown...", format_duration(remaining));
+                    thread::sleep(remaining);
+                }
+                match aoc.submit(answer)? {
+                    SubmitOutcome::RateLimited { .. } if *wait && aoc.cooldown_remaining().is_some() => (),
+                    outcome => break outcome,
+                }
+            };
+            println!("{}", outcome);
             aoc.write()?;
         },
-        Aocf::Status { .. } => status(&aoc)?,
-        Aocf::Summary { year } => summary(*year, conf.year)?,
-        Aocf::Checkout ( args ) => checkout(&mut conf, conf_hash, args)?,
-        Aocf::Init | Aocf::SetCookie { .. } | Aocf::GetCookie | Aocf::Completion { .. } => (),
+        Command::Run { submit } => solve(&mut aoc, &conf, *submit)?,
+        Command::Test { level } => {
+            let level = match level {
+                Some(1) => Level::First,
+                Some(_) => Level::Second,
+                None => aoc.level,
+            };
+            test(&mut aoc, &conf, level)?;
+        },
+        Command::Countdown { checkout, no_fetch } => {
+            let (year, day) = countdown()?;
+
+            if *checkout {
+                conf.year = year;
+                conf.day = day;
+                eprintln!("switched to year {}, day {}", year, day);
+            }
+
+            if !*no_fetch {
+                // spread out requests from everyone else waiting for the unlock
+                let delay = Duration::from_millis(1000 + random() % 4000);
+                eprintln!("fetching in {:.1}s...", delay.as_secs_f32());
+                thread::sleep(delay);
+
+                aoc = new_aoc(&conf, profile)?
+                    .year(Some(year))
+                    .day(Some(day))
+                    .init()?;
+
+                let _ = aoc.get_brief(true)?;
+                let _ = aoc.get_input(true)?;
+                aoc.write()?;
+                eprintln!("fetched brief and input for day {}, year {}", day, year);
+            }
+        },
+        Command::Leaderboard { id, year, sort, pretty, splits, day, global, csv } => {
+            let year = year.unwrap_or(conf.year);
+            let content = if let (true, Some(d)) = (*global, day) {
+                let board = leaderboard::global::fetch(&aoc.client(), year, *d)?;
+                if *pretty { render_global_markdown(&board) } else { render_global(&board) }
+            } else {
+                let board = get_leaderboard(&aoc, &conf, *id, year, profile)?;
+                match (*splits, *pretty) {
+                    (true, _) if *csv => render_splits_csv(&board, *day),
+                    (true, true) => render_splits_markdown(&board, *day),
+                    (true, false) => render_splits(&board, *day),
+                    (false, true) => render_markdown(&board, *sort),
+                    (false, false) => render(&board, *sort),
+                }
+            };
+            if *pretty {
+                make_pretty(&content)?;
+            } else {
+                print!("{}", content);
+            }
+        },
+        Command::Status { all_profiles: false } => status(&aoc)?,
+        Command::Status { all_profiles: true } => {
+            for p in profiles()? {
+                eprintln!("profile: {}", profile_name(p.as_deref()));
+                let aoc = new_aoc(&conf, p.as_deref())?
+                    .year(Some(conf.year))
+                    .day(Some(conf.day))
+                    .init()?;
+                status(&aoc)?;
+                eprintln!();
+            }
+        },
+        Command::Summary { year, sync, all_profiles } => {
+            let year = year.unwrap_or(conf.year);
+            if *sync {
+                sync_stars(&aoc, &conf, year, profile)?;
+            }
+            if *all_profiles {
+                for p in profiles()? {
+                    println!("profile: {}", profile_name(p.as_deref()));
+                    summary(year, p.as_deref())?;
+                    println!();
+                }
+            } else {
+                summary(year, profile)?;
+            }
+        },
+        Command::Events => show_events(&aoc)?,
+        Command::Whoami => whoami(&aoc)?,
+        Command::Checkout ( args ) => checkout(&mut conf, conf_hash, args)?,
+        Command::Init | Command::SetCookie { .. } | Command::GetCookie { .. } | Command::Completion { .. } => (),
     };
 
     // Update configuration if changed since start
@@ -152,6 +284,141 @@ fn run(args: &Aocf) -> Result<(), Error> {
     Ok(())
 }
 
+/// Start building an `Aoc` as configured for the CLI, for a profile
+fn new_aoc(conf: &Conf, profile: Option<&str>) -> Result<Aoc, Error> {
+    let aoc = Aoc::new()
+        .parse_cli(false)
+        .throttle(conf.throttle())
+        .on_throttle(|delay| eprintln!("throttling request, waiting {:.1}s", delay.as_secs_f32()))
+        .profile(profile);
+    Ok(match cookie(profile)? {
+        Some(c) => aoc.cookie(&c),
+        None => aoc,
+    })
+}
+
+/// Get the stored cookie for a profile, if there is one, asking for its
+/// passphrase on the terminal if it's encrypted, unless it's given by the
+/// environment
+///
+/// Each profile's cookie is only read once, as decrypting it is slow.
+fn cookie(profile: Option<&str>) -> Result<Option<String>, Error> {
+    let dir = profile_dir(profile)?;
+    let storage = match Storage::detect(&dir) {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+
+    let mut cookies = COOKIES.lock().unwrap();
+    let key = profile.map(String::from);
+    if let Some(c) = cookies.get(&key) {
+        return Ok(Some(c.clone()));
+    }
+    let passphrase = match storage {
+        Storage::EncryptedFile if env::var_os(secret::PASSPHRASE_VAR).is_none() => {
+            Some(prompt_passphrase(&format!("passphrase for {}: ", cookie_name(profile)))?)
+        },
+        _ => None,
+    };
+    let cookie = secret::open(&dir, profile, storage, passphrase.as_deref())?.get()?;
+    cookies.insert(key, cookie.clone());
+    Ok(Some(cookie))
+}
+
+/// Ask for a passphrase to encrypt a profile's cookie with, twice to make
+/// sure it's right, unless it's given by the environment
+fn new_passphrase(profile: Option<&str>) -> Result<Option<String>, Error> {
+    if env::var_os(secret::PASSPHRASE_VAR).is_some() {
+        return Ok(None);
+    }
+
+    let name = cookie_name(profile);
+    let p = prompt_passphrase(&format!("new passphrase for {}: ", name))?;
+    if p.is_empty() {
+        bail!("the passphrase can't be empty");
+    }
+    if p != prompt_passphrase(&format!("repeat the passphrase for {}: ", name))? {
+        bail!("the passphrases don't match");
+    }
+    Ok(Some(p))
+}
+
+fn prompt_passphrase(prompt: &str) -> Result<String, Error> {
+    rpassword::prompt_password(prompt).map_err(|e| {
+        format_err!("can't read the passphrase, set {} to give it instead: {}", secret::PASSPHRASE_VAR, e)
+    })
+}
+
+fn cookie_name(profile: Option<&str>) -> String {
+    match profile {
+        Some(p) => format!("the cookie for profile {}", p),
+        None => "the cookie".to_string(),
+    }
+}
+
+fn profile_dir(profile: Option<&str>) -> Result<PathBuf, Error> {
+    Ok(find_root()?.join(profile::dir(profile)?))
+}
+
+/// Get the default profile, and every named profile, which have a cookie
+fn profiles() -> Result<Vec<Option<String>>, Error> {
+    let named = profile::list(find_root()?)?.into_iter().map(Some);
+    let mut profiles = vec![];
+    for p in std::iter::once(None).chain(named) {
+        if Storage::detect(profile_dir(p.as_deref())?).is_some() {
+            profiles.push(p);
+        }
+    }
+    Ok(profiles)
+}
+
+fn profile_name(profile: Option<&str>) -> &str {
+    profile.unwrap_or("(default)")
+}
+
+/// Get the year and day of the puzzle released today
+fn today() -> Result<(i32, u32), Error> {
+    match current_puzzle(Utc::now()) {
+        Some(p) => Ok(p),
+        None => bail!("no puzzle has been released today, events run in December"),
+    }
+}
+
+/// Show a live countdown until the next puzzle unlocks, returning its year
+/// and day once it has
+fn countdown() -> Result<(i32, u32), Error> {
+    let (year, day, release) = next_release(Utc::now());
+    let mut stderr = io::stderr();
+
+    loop {
+        let remaining = (release - Utc::now()).to_std().unwrap_or_default();
+        if remaining.as_millis() == 0 {
+            break;
+        }
+        eprint!("\r{} until day {}, year {} ", format_duration(remaining), day, year);
+        stderr.flush()?;
+        thread::sleep(remaining.min(Duration::from_secs(1)));
+    }
+
+    eprintln!("\nday {}, year {} is unlocked", day, year);
+    Ok((year, day))
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs() + u64::from(duration.subsec_nanos() > 0);
+    let (d, h, m, s) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    if d > 0 {
+        format!("{}d {:02}:{:02}:{:02}", d, h, m, s)
+    } else {
+        format!("{:02}:{:02}:{:02}", h, m, s)
+    }
+}
+
+/// Get a random number, without pulling in a dependency
+fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
 fn strinfo(text: &str) -> Result<(), Error> {
     println!("{} bytes, {} lines", text.len(), text.lines().count());
     Ok(())
@@ -190,12 +457,98 @@ fn pager(conf: &Conf, text: &str) -> Result<(), Error> {
     Ok(())
 }
 
+fn solve(aoc: &mut Aoc, conf: &Conf, submit: bool) -> Result<(), Error> {
+    let input = aoc.get_input(false)?;
+    aoc.write()?;
+
+    let run = run_solver(conf, &input)?;
+    if !run.success {
+        bail!("solver exited unsuccessfully");
+    }
+    let answer = match run.answer {
+        Some(a) => a,
+        None => bail!("solver gave no answer"),
+    };
+
+    eprintln!("level: {}", aoc.level);
+    eprintln!("time:  {}", format_elapsed(run.elapsed));
+    println!("{}", answer);
+
+    let history: Vec<_> = aoc.submissions_for(aoc.level).collect();
+    if !history.is_empty() {
+        eprintln!("previous submissions:");
+        for s in history {
+            eprintln!("    {} {:<16} {}", s.timestamp.format("%Y-%m-%d %H:%M:%S"), s.answer, s.outcome);
+        }
+    }
+
+    if submit {
+        let outcome = aoc.submit(&answer)?;
+        println!("{}", outcome);
+        aoc.write()?;
+    } else if let Err(e) = aoc.check_answer(&answer) {
+        eprintln!("warning: {}", e);
+    }
+
+    Ok(())
+}
+
+fn test(aoc: &mut Aoc, conf: &Conf, level: Level) -> Result<(), Error> {
+    let examples = aoc.examples(level)?;
+    aoc.write()?;
+    if examples.is_empty() {
+        bail!("no examples found in the brief for the {} level", level);
+    }
+
+    println!("{:>3}  {:<16} {:<16} {:>10}  result", "#", "expected", "output", "time");
+    let mut failed = 0;
+    for (i, example) in examples.iter().enumerate() {
+        let run = run_solver(conf, &example.input)?;
+        let output = run.answer.as_deref().unwrap_or("-");
+        let result = match &example.answer {
+            _ if !run.success => "error",
+            None => "skip",
+            Some(a) if a == output => "pass",
+            Some(_) => "fail",
+        };
+        if result == "fail" || result == "error" {
+            failed += 1;
+        }
+        println!(
+            "{:>3}  {:<16} {:<16} {:>10}  {}",
+            i + 1,
+            example.answer.as_deref().unwrap_or("?"),
+            output,
+            format_elapsed(run.elapsed),
+            result,
+        );
+    }
+
+    if failed > 0 {
+        bail!("{} of {} examples failed", failed, examples.len());
+    }
+    Ok(())
+}
+
+fn get_leaderboard(aoc: &Aoc, conf: &Conf, id: Option<u64>, year: i32, profile: Option<&str>) -> Result<Leaderboard, Error> {
+    let id = match id.or(conf.leaderboard) {
+        Some(id) => id,
+        None => bail!("no leaderboard ID given, use --id or set `leaderboard` in .aocf/config"),
+    };
+
+    let cache_dir = leaderboard::default_cache_dir(profile)?;
+    if let Some(refresh) = leaderboard::next_refresh(&cache_dir, year, id).filter(|d| !d.is_zero()) {
+        eprintln!("using cached leaderboard, can be refreshed in {}", format_duration(refresh));
+    }
+    Ok(leaderboard::get(&aoc.client(), year, id, &cache_dir)?)
+}
+
 fn status(aoc: &Aoc) -> Result<(), Error> {
     if let (Some(d), Some(y)) = (aoc.day, aoc.year) {
         eprintln!("{:<6} {}", "year:", y);
         eprintln!("day:   {}", d);
     } else {
-        bail!("day or year not set")
+        bail!(aocf::Error::DayOrYearNotSet)
     }
     eprintln!("level: {}", aoc.level);
     if let Some(t) = &aoc.title {
@@ -219,31 +572,73 @@ fn status(aoc: &Aoc) -> Result<(), Error> {
     Ok(())
 }
 
-fn summary(arg_year: Option<i32>, conf_year: i32) -> Result<(), Error> {
-    let year = arg_year.unwrap_or(conf_year);
+/// Reconcile the stars in the cache for a year with the calendar
+fn sync_stars(aoc: &Aoc, conf: &Conf, year: i32, profile: Option<&str>) -> Result<(), Error> {
+    let calendar = calendar::fetch(&aoc.client(), year)?;
+
+    for (day, stars) in &calendar.days {
+        let mut cached = new_aoc(conf, profile)?
+            .year(Some(year))
+            .day(Some(*day))
+            .init()?;
+        let before = cached.stars.unwrap_or_default();
+        // unsolved days without a cache file are left alone, rather than
+        // being written out
+        if cached.sync_stars(*stars) {
+            cached.write()?;
+            eprintln!("day {}: {} -> {} stars", day, before, stars);
+        }
+    }
+
+    eprintln!("{} stars in {}", calendar.total_stars(), year);
+    Ok(())
+}
+
+fn summary(year: i32, profile: Option<&str>) -> Result<(), Error> {
+    let cache_dir = find_root()?.join(profile::dir(profile)?).join("cache");
+    if !cache_dir.is_dir() {
+        return Ok(());
+    }
 
-    let mut configs: Vec<_> = fs::read_dir(find_root()?.join(".aocf/cache"))?
-        .map(|r| r.map(|e| e.path()))
-        .flatten()
-        .map(Aoc::load_json_from)
-        .flatten()
+    let mut configs: Vec<_> = fs::read_dir(cache_dir)?
+        .flat_map(|r| r.map(|e| e.path()))
+        .flat_map(Aoc::load_json_from)
         .filter(|a| a.year == Some(year))
         .collect();
 
-    configs.sort_by(|a, b| a.day.cmp(&b.day));
+    configs.sort_by_key(|a| a.day);
 
     configs
         .iter()
         .for_each(|p| {
-            if let (Some(y), Some(d), Some(t), Some(s)) = (p.year, p.day, &p.title, p.stars) {
+            if let (Some(y), Some(d), Some(s)) = (p.year, p.day, p.stars) {
                 let s: String = "*".repeat(s.into());
-                println!("{} {:2} {:2} {}", y, d, s, t);
+                println!("{} {:2} {:2} {}", y, d, s, p.title.as_deref().unwrap_or_default());
             }
         });
 
     Ok(())
 }
 
+fn show_events(aoc: &Aoc) -> Result<(), Error> {
+    let events = events::fetch(&aoc.client())?;
+    let mut possible = 0;
+
+    for (year, stars) in events.years.iter().rev() {
+        let max = event_days(*year).unwrap_or_default() * 2;
+        possible += max;
+        // an approximate progress bar, a `*` for every two stars and a `.`
+        // for an odd one, the stars of each day aren't known without the
+        // calendar
+        let progress = format!("{}{}", "*".repeat(*stars as usize / 2), ".".repeat(*stars as usize % 2));
+        let summary = format!("aocf summary --year {}", year);
+        println!("{} {:>2}/{:2} {:25} {}", year, stars, max, progress, summary);
+    }
+
+    println!("total {}/{} stars", events.total_stars(), possible);
+    Ok(())
+}
+
 fn init() -> Result<(), Error> {
     let conf_path = env::current_dir()?.join(".aocf");
     fs::create_dir_all(&conf_path)?;
@@ -262,7 +657,12 @@ fn init() -> Result<(), Error> {
 }
 
 fn checkout(conf: &mut Conf, conf_hash: u64, args: &AocfTimeDateOpts) -> Result<(), Error> {
-    let (day, year) = args.get_day_year();
+    let (day, year) = if args.now {
+        let (y, d) = today()?;
+        (Some(d), Some(y))
+    } else {
+        args.get_day_year()
+    };
 
     if let Some(d) = day {
         conf.day = d;
@@ -281,47 +681,164 @@ fn checkout(conf: &mut Conf, conf_hash: u64, args: &AocfTimeDateOpts) -> Result<
     Ok(())
 }
 
-fn set_cookie(cookie: &str) -> Result<(), Error> {
-    let cookie_path = find_root()?.join(".aocf/cookie");
-    let mut file = fs::File::create(cookie_path)?;
-
-    // write out a .gitignore to avoid committing the cookie data (which
-    // would be very insecure!)
+/// Write out a .gitignore to avoid committing the cookie data (which would
+/// be very insecure!), adding any entries missing from an existing one
+fn write_gitignore() -> Result<(), Error> {
     let gitignore_path = find_root()?.join(".aocf/.gitignore");
-    if !gitignore_path.exists() {
-        let mut gitignore = fs::File::create(gitignore_path)?;
-        gitignore.write_all(b"cookie\n")?;
+    let existing = fs::read_to_string(&gitignore_path).unwrap_or_default();
+    let missing: String = GITIGNORE.iter()
+        .filter(|entry| !existing.lines().any(|l| l.trim() == **entry))
+        .map(|entry| format!("{}\n", entry))
+        .collect();
+
+    if !missing.is_empty() {
+        let separator = if existing.is_empty() || existing.ends_with('\n') { "" } else { "\n" };
+        let mut gitignore = fs::OpenOptions::new().create(true).append(true).open(gitignore_path)?;
+        gitignore.write_all(format!("{}{}", separator, missing).as_bytes())?;
     }
+    Ok(())
+}
+
+fn set_cookie(cookie: &str, profile: Option<&str>, storage: Option<CookieStorage>) -> Result<(), Error> {
+    let dir = profile_dir(profile)?;
+    let storage = storage.map(Storage::from)
+        .or_else(|| Storage::detect(&dir))
+        .unwrap_or(Storage::Plaintext);
+
+    let passphrase = match storage {
+        Storage::EncryptedFile => new_passphrase(profile)?,
+        _ => None,
+    };
 
-    Ok(file.write_all(cookie.as_bytes())?)
+    write_gitignore()?;
+    secret::set_cookie(&dir, profile, storage, passphrase.as_deref(), cookie)?;
+    COOKIES.lock().unwrap().insert(profile.map(String::from), cookie.to_string());
+
+    // the cookie is stored even if it can't be validated, e.g. when offline
+    if let Err(e) = validate_cookie(profile) {
+        eprintln!("warning: {}", e);
+    }
+    Ok(())
 }
 
-fn get_cookie() -> Result<(), Error> {
-    let cookie_store_dir = match home_dir() {
-        None => bail!("can't get home directory"),
-        Some(d) => {
-            if let Some(p) = d.join(".mozilla/firefox/*.default/cookies.sqlite").to_str() {
-                match glob(p) {
-                    Ok(mut path) => path.next(),
-                    Err(e) => bail!("{:?}", e),
-                }
-            } else {
-                bail!("can't get cookie store path");
-            }
-        }
+/// Check the stored cookie with the website, getting the account it's for
+fn check_cookie(aoc: &Aoc) -> Result<Account, Error> {
+    match account::whoami(&aoc.client()) {
+        Err(aocf::Error::Unauthenticated) => {
+            bail!("the session cookie isn't valid, or has expired, please run set-cookie or get-cookie")
+        },
+        result => Ok(result?),
+    }
+}
+
+fn validate_cookie(profile: Option<&str>) -> Result<(), Error> {
+    let conf = find_config()?;
+    let aoc = new_aoc(&conf, profile)?.init()?;
+    let account = check_cookie(&aoc)?;
+    eprintln!("logged in as {}", account.name);
+    Ok(())
+}
+
+fn whoami(aoc: &Aoc) -> Result<(), Error> {
+    let account = check_cookie(aoc)?;
+    let supporter = if account.supporter { " (AoC++)" } else { "" };
+    println!("{}{}", account.name, supporter);
+    Ok(())
+}
+
+/// Warn if a cookie has expired, or expires soon
+fn check_expiry(expiry: DateTime<Utc>) {
+    let remaining = expiry - Utc::now();
+    if remaining <= chrono::Duration::zero() {
+        eprintln!("warning: the session cookie expired on {}, please log in again", expiry.date_naive());
+    } else if remaining < chrono::Duration::days(EXPIRY_WARNING_DAYS) {
+        eprintln!("warning: the session cookie expires on {}", expiry.date_naive());
+    }
+}
+
+/// Find the cookie store for a browser profile, or the default profile
+fn find_cookie_store(browser: Browser, profile: Option<&str>) -> Result<PathBuf, Error> {
+    if browser == Browser::Firefox {
+        return firefox::find_cookie_store(profile);
+    }
+
+    let config = match config_dir() {
+        None => bail!("can't get config directory"),
+        Some(d) => d,
+    };
+    let profile = config.join("chromium").join(profile.unwrap_or("Default"));
+    let searched = [profile.join("Network/Cookies"), profile.join("Cookies")];
+
+    match searched.iter().find(|p| p.is_file()) {
+        Some(p) => Ok(p.clone()),
+        None => {
+            let searched: Vec<_> = searched.iter().map(|p| format!("    {}", p.display())).collect();
+            bail!("no Chromium cookie store found, searched:\n{}", searched.join("\n"))
+        },
+    }
+}
+
+fn list_profiles() -> Result<(), Error> {
+    for profile in firefox::find_profiles()? {
+        let default = if profile.default { " (default)" } else { "" };
+        let store = if profile.cookie_store().is_file() { "" } else { ", no cookie store" };
+        println!("{}{}: {}{}", profile.name, default, profile.path.display(), store);
+    }
+    Ok(())
+}
+
+/// Look up the secret Chromium encrypts cookies with in the keyring
+fn chromium_keyring_secret() -> Option<String> {
+    let output = process::Command::new("secret-tool")
+        .args(["lookup", "application", "chromium"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let secret = String::from_utf8(output.stdout).ok()?;
+    match secret.trim() {
+        "" => None,
+        s => Some(s.to_string()),
+    }
+}
+
+fn get_cookie(
+    browser: Browser,
+    store: Option<&Path>,
+    browser_profile: Option<&str>,
+    keyring_secret: Option<&str>,
+    profile: Option<&str>,
+    storage: Option<CookieStorage>,
+) -> Result<(), Error> {
+    let path = match store {
+        Some(p) => p.to_path_buf(),
+        None => find_cookie_store(browser, browser_profile)?,
     };
+    eprintln!("found cookie store: {}", path.display());
 
-    // copy the cookie store to a temporary location, if firefox is open, the
-    // store will be locked
+    // copy the cookie store to a temporary location, if the browser is open,
+    // the store will be locked
     let tmp_dir = tempdir()?;
     let tmp_path = tmp_dir.path().join("cookies.sqlite");
-    if let Some(Ok(path)) = cookie_store_dir {
-        eprintln!("found cookie store: {}", path.display());
-        fs::copy(&path, &tmp_path)?;
-    } else {
-        bail!("couldn't get cookie store path");
-    }
+    fs::copy(&path, &tmp_path)?;
 
-    let cookie_value = get_session_cookie(&tmp_path)?;
-    set_cookie(&cookie_value)
+    let cookie_value = match browser {
+        Browser::Firefox => {
+            // the expiry is only used to warn, an unknown expiry isn't an error
+            if let Ok(expiry) = get_session_cookie_expiry(&tmp_path) {
+                check_expiry(expiry);
+            }
+            get_session_cookie(&tmp_path)?
+        },
+        Browser::Chromium => match keyring_secret {
+            Some(s) => get_chromium_session_cookie(&tmp_path, Some(s))?,
+            // only look up the secret if it turns out to be needed
+            None => get_chromium_session_cookie(&tmp_path, None).or_else(|e| {
+                match chromium_keyring_secret() {
+                    Some(s) => get_chromium_session_cookie(&tmp_path, Some(&s)),
+                    None => Err(e),
+                }
+            })?,
+        },
+    };
+    set_cookie(&cookie_value, profile, storage)
 }
@@ -1,6 +1,8 @@
-use chrono::{Datelike, Utc};
-use clap::{Args, CommandFactory, Parser};
+use crate::leaderboard::Sort;
+use aocf::Storage;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
+use std::path::PathBuf;
 
 pub fn generate_completion(shell: Shell) {
     clap_complete::generate(shell, &mut Aocf::command(), "aocf", &mut std::io::stdout());
@@ -9,7 +11,17 @@ pub fn generate_completion(shell: Shell) {
 /// Advent of Code Swiss army knife
 #[derive(Parser, Debug)]
 #[clap(version)]
-pub enum Aocf {
+pub struct Aocf {
+    /// Account profile to use, each having its own cookie and cache
+    #[clap(long)]
+    pub profile: Option<String>,
+
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
     /// Switch to a specified year and day
     Checkout(AocfTimeDateOpts),
 
@@ -59,6 +71,10 @@ pub enum Aocf {
     Submit {
         /// Your answer
         answer: String,
+
+        /// Wait until the cooldown from a previous submission has passed
+        #[clap(short, long)]
+        wait: bool,
     },
 
     /// Fetch brief and input data, if available
@@ -76,16 +92,96 @@ pub enum Aocf {
         day: Option<u32>,
     },
 
+    /// Run the configured solver on the input for the current problem
+    Run {
+        /// Submit the answer given by the solver
+        #[clap(short, long)]
+        submit: bool,
+    },
+
+    /// Run the configured solver against the examples given in the brief
+    Test {
+        /// Level to test, defaults to the current level
+        #[clap(short, long, value_parser = clap::value_parser!(u8).range(1..=2))]
+        level: Option<u8>,
+    },
+
+    /// Wait for the next puzzle to unlock, then fetch its brief and input
+    #[clap(alias = "wait")]
+    Countdown {
+        /// Check out the new puzzle once it unlocks
+        #[clap(short, long)]
+        checkout: bool,
+
+        /// Only count down, don't fetch
+        #[clap(short, long)]
+        no_fetch: bool,
+    },
+
+    /// Show a private leaderboard, or the global leaderboard for a day
+    Leaderboard {
+        /// Leaderboard ID, defaults to the ID set in the configuration
+        #[clap(short, long)]
+        id: Option<u64>,
+
+        /// Specify the challenge year to view
+        #[clap(short, long)]
+        year: Option<i32>,
+
+        /// Order to rank members in
+        #[clap(short, long, value_enum, default_value = "score")]
+        sort: Sort,
+
+        /// View pretty
+        #[clap(short, long)]
+        pretty: bool,
+
+        /// Show the time taken for each part, from the puzzle unlocking
+        #[clap(long)]
+        splits: bool,
+
+        /// Only show splits for this day, or show the global leaderboard
+        /// for this day
+        #[clap(short, long)]
+        day: Option<u32>,
+
+        /// Show the global leaderboard for a day
+        #[clap(short, long, requires = "day", conflicts_with_all = &["id", "splits"])]
+        global: bool,
+
+        /// Output splits as CSV
+        #[clap(long, requires = "splits", conflicts_with = "pretty")]
+        csv: bool,
+    },
+
     /// Get current status
-    Status,
+    Status {
+        /// Show the status for every profile
+        #[clap(short, long)]
+        all_profiles: bool,
+    },
 
     /// Get summary of challenges and stars
     Summary {
         /// Specify the challenge year to view
         #[clap(short, long)]
         year: Option<i32>,
+
+        /// Update stars in the cache from the calendar on the website
+        #[clap(short, long)]
+        sync: bool,
+
+        /// Show the summary for every profile
+        #[clap(short, long, conflicts_with = "sync")]
+        all_profiles: bool,
     },
 
+    /// Get an overview of stars gained in every year's event
+    Events,
+
+    /// Check the session cookie, showing the account it's for
+    Whoami,
+
     /// Initialise an aocf repository
     Init,
 
@@ -93,10 +189,42 @@ pub enum Aocf {
     SetCookie {
         /// Contents of authentication token to store
         token: String,
+
+        /// How to store the token, defaults to how it's already stored, or
+        /// plaintext
+        #[clap(long, value_enum)]
+        storage: Option<CookieStorage>,
     },
 
-    /// Get authentication token from firefox cookie store
-    GetCookie,
+    /// Get authentication token from a browser's cookie store
+    GetCookie {
+        /// Browser to get the token from
+        #[clap(short, long, value_enum, default_value = "firefox")]
+        browser: Browser,
+
+        /// Path to the cookie store, if not the browser's default
+        #[clap(short, long)]
+        store: Option<PathBuf>,
+
+        /// Browser profile to use, by name or profile directory, defaults to
+        /// the browser's default profile
+        #[clap(long, conflicts_with = "store")]
+        browser_profile: Option<String>,
+
+        /// List Firefox profiles
+        #[clap(short, long, conflicts_with_all = &["store", "browser-profile"])]
+        list_profiles: bool,
+
+        /// Keyring secret Chromium encrypts cookies with, looked up with
+        /// secret-tool if not given
+        #[clap(short, long)]
+        keyring_secret: Option<String>,
+
+        /// How to store the token, defaults to how it's already stored, or
+        /// plaintext
+        #[clap(long, value_enum)]
+        storage: Option<CookieStorage>,
+    },
 
     /// Generate shell completion script
     Completion {
@@ -106,11 +234,39 @@ pub enum Aocf {
     },
 }
 
+/// Browser to get the authentication token from
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Browser {
+    Firefox,
+    Chromium,
+}
+
+/// How to store the authentication token
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CookieStorage {
+    /// In a file only readable by the user
+    Plaintext,
+    /// In the freedesktop Secret Service, e.g. GNOME Keyring
+    SecretService,
+    /// In a file encrypted with the passphrase in AOCF_PASSPHRASE
+    Encrypted,
+}
+
+impl From<CookieStorage> for Storage {
+    fn from(storage: CookieStorage) -> Self {
+        match storage {
+            CookieStorage::Plaintext => Storage::Plaintext,
+            CookieStorage::SecretService => Storage::SecretService,
+            CookieStorage::Encrypted => Storage::EncryptedFile,
+        }
+    }
+}
+
 #[derive(Args, Debug)]
 pub struct AocfTimeDateOpts {
     /// Check out current day and year
     #[clap(short, long, conflicts_with_all = &["problem-day", "problem-year", "day", "year"])]
-    now: bool,
+    pub now: bool,
 
     /// Problem day
     #[clap(short, long)]
@@ -130,12 +286,10 @@ pub struct AocfTimeDateOpts {
 }
 
 impl AocfTimeDateOpts {
+    /// Get the day and year given, `--now` being handled by the caller as
+    /// it may fail
     pub fn get_day_year(&self) -> (Option<u32>, Option<i32>) {
         match self {
-            Self { now: true, .. } => {
-                let now = Utc::now();
-                (Some(now.day()), Some(now.year()))
-            },
             Self { day: Some(d), year, .. } => {
                 (Some(*d), *year)
             },
@@ -1,13 +1,18 @@
-use failure::Error;
+use aocf::throttle::DEFAULT_INTERVAL;
+use anyhow::Error;
 use std::collections::hash_map::DefaultHasher;
 use std::fs::{File, read_to_string};
 use std::hash::{Hash, Hasher};
 use std::io::Write;
 use std::path::Path;
+use std::time::Duration;
 
-#[derive(Serialize, Deserialize, Debug, Hash)]
-enum ExecMode {
+/// How input data is provided to the solver
+#[derive(Serialize, Deserialize, Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum ExecMode {
+    /// Piped to the solver's standard input
     Stdin,
+    /// Written to a file, the path of which is given as the last argument
     File,
 }
 
@@ -17,8 +22,13 @@ pub struct Conf {
     pub day: u32,
     editor: String,
     pub pager: String,
-    exec: Option<String>,
-    mode: Option<ExecMode>,
+    /// Command to run the solver
+    pub exec: Option<String>,
+    pub mode: Option<ExecMode>,
+    /// Minimum interval between requests in seconds, 0 disables throttling
+    throttle: Option<u64>,
+    /// ID of the private leaderboard to show by default
+    pub leaderboard: Option<u64>,
 }
 
 impl Default for Conf {
@@ -30,6 +40,8 @@ impl Default for Conf {
             pager: "less".into(),
             exec: None,
             mode: None,
+            throttle: None,
+            leaderboard: None,
         }
     }
 }
@@ -47,6 +59,15 @@ impl Conf {
         Ok(())
     }
 
+    /// Get the minimum interval between requests, if throttling is enabled
+    pub fn throttle(&self) -> Option<Duration> {
+        match self.throttle {
+            None => Some(DEFAULT_INTERVAL),
+            Some(0) => None,
+            Some(s) => Some(Duration::from_secs(s)),
+        }
+    }
+
     pub fn calc_hash(&self) -> u64 {
         let mut s = DefaultHasher::new();
         self.hash(&mut s);
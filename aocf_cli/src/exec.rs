@@ -0,0 +1,133 @@
+use crate::conf::{Conf, ExecMode};
+use anyhow::{Error, bail, format_err};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+/// Result of running the solver
+#[derive(Debug)]
+pub struct Run {
+    /// The last line of output from the solver
+    pub answer: Option<String>,
+    pub elapsed: Duration,
+    pub success: bool,
+}
+
+/// Run the solver configured by `exec`, providing input data as configured by
+/// `mode`
+pub fn run_solver(conf: &Conf, input: &str) -> Result<Run, Error> {
+    let exec = match &conf.exec {
+        Some(e) => e,
+        None => bail!("no solver configured, set `exec` in .aocf/config"),
+    };
+    if exec.trim().is_empty() {
+        bail!("solver command is empty");
+    }
+
+    let tmp_dir = tempdir()?;
+    let mode = conf.mode.unwrap_or(ExecMode::Stdin);
+    let mut command = match mode {
+        ExecMode::Stdin => {
+            let mut command = shell_command(exec, None);
+            command.stdin(Stdio::piped());
+            command
+        },
+        ExecMode::File => {
+            let path = tmp_dir.path().join("input");
+            std::fs::write(&path, input)?;
+            let mut command = shell_command(exec, Some(&path));
+            command.stdin(Stdio::null());
+            command
+        },
+    };
+    command.stdout(Stdio::piped())
+        .stderr(Stdio::inherit());
+
+    let start = Instant::now();
+    let mut child = command.spawn()
+        .map_err(|e| format_err!("running `{}`: {}", exec, e))?;
+    // write from a separate thread, so a solver producing output before
+    // reading all of its input can't deadlock
+    let writer = child.stdin.take().map(|mut stdin| {
+        let input = input.to_string();
+        thread::spawn(move || stdin.write_all(input.as_bytes()))
+    });
+    let output = child.wait_with_output()?;
+    if let Some(writer) = writer {
+        // the solver may legitimately exit without reading all input
+        let _ = writer.join();
+    }
+    let elapsed = start.elapsed();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let answer = stdout.lines()
+        .map(str::trim)
+        .rev()
+        .find(|l| !l.is_empty())
+        .map(str::to_string);
+
+    Ok(Run { answer, elapsed, success: output.status.success() })
+}
+
+/// Build a command running `exec` with the shell, so it may use quoting,
+/// with an input file as the last argument
+#[cfg(not(windows))]
+fn shell_command(exec: &str, file: Option<&Path>) -> Command {
+    let mut command = Command::new("sh");
+    // the file is passed as a positional parameter, so it needn't be quoted
+    command.arg("-c")
+        .arg(format!("{} \"$@\"", exec))
+        .arg("sh")
+        .args(file);
+    command
+}
+
+#[cfg(windows)]
+fn shell_command(exec: &str, file: Option<&Path>) -> Command {
+    let mut command = Command::new("cmd");
+    command.arg("/C").arg(exec).args(file);
+    command
+}
+
+/// Format a duration for display with a sensible unit
+pub fn format_elapsed(duration: Duration) -> String {
+    let secs = duration.as_secs_f64();
+    if secs >= 1.0 {
+        format!("{:.2}s", secs)
+    } else if secs >= 0.001 {
+        format!("{:.1}ms", secs * 1e3)
+    } else {
+        format!("{:.0}µs", secs * 1e6)
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+
+    fn conf(exec: &str, mode: ExecMode) -> Conf {
+        let mut conf = Conf::default();
+        conf.exec = Some(exec.to_string());
+        conf.mode = Some(mode);
+        conf
+    }
+
+    #[test]
+    fn test_run_solver() {
+        let run = run_solver(&conf("printf '%s\\n' 'a b'", ExecMode::Stdin), "").unwrap();
+        assert_eq!(run.answer.as_deref(), Some("a b"));
+        assert!(run.success);
+
+        let run = run_solver(&conf("wc -l", ExecMode::Stdin), "1\n2\n").unwrap();
+        assert_eq!(run.answer.as_deref(), Some("2"));
+
+        let run = run_solver(&conf("cat", ExecMode::File), "1\n2\n").unwrap();
+        assert_eq!(run.answer.as_deref(), Some("2"));
+
+        assert!(!run_solver(&conf("exit 1", ExecMode::Stdin), "").unwrap().success);
+        assert!(run_solver(&conf(" ", ExecMode::Stdin), "").is_err());
+    }
+}
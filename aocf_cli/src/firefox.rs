@@ -0,0 +1,232 @@
+//! Discovery of Firefox profiles
+//!
+//! Profiles are listed in `profiles.ini` in Firefox's data directory, which
+//! differs for Flatpak and Snap installs, as well as by platform.
+use anyhow::{Error, bail};
+use dirs::{data_dir, home_dir};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+/// A Firefox profile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub path: PathBuf,
+    /// Whether this is the profile Firefox uses by default
+    pub default: bool,
+}
+
+impl Profile {
+    /// Get the path to the profile's cookie store
+    pub fn cookie_store(&self) -> PathBuf {
+        self.path.join("cookies.sqlite")
+    }
+}
+
+/// Get the directories Firefox may keep profiles in
+pub fn profile_roots() -> Vec<PathBuf> {
+    let mut roots = vec![];
+    if let Some(home) = home_dir() {
+        roots.push(home.join(".mozilla/firefox"));
+        roots.push(home.join(".var/app/org.mozilla.firefox/.mozilla/firefox"));
+        roots.push(home.join("snap/firefox/common/.mozilla/firefox"));
+    }
+    if let Some(data) = data_dir() {
+        if cfg!(target_os = "macos") {
+            roots.push(data.join("Firefox"));
+        } else if cfg!(windows) {
+            roots.push(data.join("Mozilla/Firefox"));
+        }
+    }
+    roots
+}
+
+/// Parse the profiles listed in `profiles.ini`, relative paths being
+/// relative to the directory it's in
+///
+/// The default profile is given by an `Install` section for newer versions
+/// of Firefox, or otherwise by the `Default` flag of a `Profile` section.
+pub fn parse_profiles(ini: &str, root: &Path) -> Vec<Profile> {
+    let mut sections: Vec<(String, Vec<(String, String)>)> = vec![];
+    for line in ini.lines().map(str::trim) {
+        if line.starts_with('[') && line.ends_with(']') {
+            sections.push((line[1..line.len() - 1].to_string(), vec![]));
+        } else if let (Some((key, value)), Some((_, entries))) = (line.split_once('='), sections.last_mut()) {
+            entries.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let get = |entries: &[(String, String)], key: &str| {
+        entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+    };
+
+    let install_default = sections.iter()
+        .filter(|(name, _)| name.starts_with("Install"))
+        .find_map(|(_, entries)| get(entries, "Default"));
+
+    let mut profiles: Vec<_> = sections.iter()
+        .filter(|(name, _)| name.starts_with("Profile"))
+        .filter_map(|(_, entries)| {
+            let path = get(entries, "Path")?;
+            let relative = get(entries, "IsRelative").as_deref() != Some("0");
+            let default = match &install_default {
+                Some(d) => *d == path,
+                None => get(entries, "Default").as_deref() == Some("1"),
+            };
+            Some(Profile {
+                name: get(entries, "Name").unwrap_or_else(|| path.clone()),
+                path: if relative { root.join(&path) } else { PathBuf::from(&path) },
+                default,
+            })
+        })
+        .collect();
+
+    // the default profile first
+    profiles.sort_by_key(|p| !p.default);
+    profiles
+}
+
+/// Find all Firefox profiles, failing with the places searched if there are
+/// none
+pub fn find_profiles() -> Result<Vec<Profile>, Error> {
+    let roots = profile_roots();
+    let profiles: Vec<_> = roots.iter()
+        .flat_map(|root| {
+            let ini = read_to_string(root.join("profiles.ini")).unwrap_or_default();
+            parse_profiles(&ini, root)
+        })
+        .collect();
+
+    if profiles.is_empty() {
+        let searched: Vec<_> = roots.iter()
+            .map(|r| format!("    {}", r.join("profiles.ini").display()))
+            .collect();
+        bail!("no Firefox profiles found, searched:\n{}", searched.join("\n"));
+    }
+
+    Ok(profiles)
+}
+
+/// Find the cookie store for a profile, given by name or by the path to a
+/// profile directory, or for the default profile
+///
+/// If the default profile has no cookie store, the first profile with one
+/// is used.
+pub fn find_cookie_store(profile: Option<&str>) -> Result<PathBuf, Error> {
+    if let Some(dir) = profile.map(Path::new).filter(|p| p.is_dir()) {
+        let store = dir.join("cookies.sqlite");
+        if !store.is_file() {
+            bail!("no cookie store found in profile directory {}", dir.display());
+        }
+        return Ok(store);
+    }
+
+    select_cookie_store(&find_profiles()?, profile)
+}
+
+/// Choose the cookie store for a profile, given by name or profile directory
+/// name, or for the default profile, from the profiles found
+fn select_cookie_store(profiles: &[Profile], profile: Option<&str>) -> Result<PathBuf, Error> {
+    let found = match profile {
+        Some(name) => profiles.iter().find(|p| {
+            p.name == name || p.path.file_name().is_some_and(|f| f == name)
+        }),
+        None => profiles.iter().find(|p| p.cookie_store().is_file()),
+    };
+
+    match (found, profile) {
+        (Some(p), _) if p.cookie_store().is_file() => Ok(p.cookie_store()),
+        (Some(p), _) => bail!("no cookie store found in profile {} at {}", p.name, p.path.display()),
+        (None, Some(name)) => {
+            let names: Vec<_> = profiles.iter().map(|p| p.name.as_str()).collect();
+            bail!("no Firefox profile named {}, found: {}", name, names.join(", "))
+        },
+        (None, None) => {
+            let searched: Vec<_> = profiles.iter()
+                .map(|p| format!("    {}", p.cookie_store().display()))
+                .collect();
+            bail!("no Firefox cookie store found, searched:\n{}", searched.join("\n"))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+    use tempfile::tempdir;
+
+    const PROFILES: &str = "
+[Install4F96D1932A9F858E]
+Default=Profiles/abcd1234.work
+Locked=1
+
+[Profile1]
+Name=default
+IsRelative=1
+Path=Profiles/wxyz9876.default
+Default=1
+
+[Profile0]
+Name=work
+IsRelative=1
+Path=Profiles/abcd1234.work
+
+[Profile2]
+Name=elsewhere
+IsRelative=0
+Path=/mnt/firefox/elsewhere
+
+[General]
+StartWithLastProfile=1
+Version=2
+";
+
+    #[test]
+    fn test_parse_profiles() {
+        let root = Path::new("/home/user/.mozilla/firefox");
+        let profiles = parse_profiles(PROFILES, root);
+
+        // the Install section's default comes first, over the Default flag
+        assert_eq!(profiles[0], Profile {
+            name: "work".to_string(),
+            path: root.join("Profiles/abcd1234.work"),
+            default: true,
+        });
+        assert_eq!(profiles.iter().filter(|p| p.default).count(), 1);
+        assert_eq!(profiles[1].name, "default");
+        assert_eq!(profiles[2].path, PathBuf::from("/mnt/firefox/elsewhere"));
+    }
+
+    #[test]
+    fn test_parse_profiles_default_flag() {
+        // older versions of Firefox have no Install section
+        let ini = "[Profile0]\nName=a\nPath=a.a\n\n[Profile1]\nName=b\nPath=b.b\nDefault=1\n";
+        let profiles = parse_profiles(ini, Path::new("root"));
+        let names: Vec<_> = profiles.iter().map(|p| (p.name.as_str(), p.default)).collect();
+        assert_eq!(names, vec![("b", true), ("a", false)]);
+        assert_eq!(profiles[0].path, Path::new("root/b.b"));
+    }
+
+    #[test]
+    fn test_select_cookie_store() {
+        let tmp = tempdir().unwrap();
+        let profiles = parse_profiles(PROFILES, tmp.path());
+        for p in &profiles[..2] {
+            create_dir_all(&p.path).unwrap();
+            write(p.cookie_store(), "").unwrap();
+        }
+
+        let work = profiles[0].cookie_store();
+        let default = profiles[1].cookie_store();
+        assert_eq!(select_cookie_store(&profiles, None).unwrap(), work);
+        assert_eq!(select_cookie_store(&profiles, Some("default")).unwrap(), default);
+        assert_eq!(select_cookie_store(&profiles, Some("wxyz9876.default")).unwrap(), default);
+        assert!(select_cookie_store(&profiles, Some("elsewhere")).is_err());
+        assert!(select_cookie_store(&profiles, Some("missing")).is_err());
+
+        // a profile directory given by path
+        let dir = profiles[1].path.to_str().unwrap();
+        assert_eq!(find_cookie_store(Some(dir)).unwrap(), default);
+    }
+}
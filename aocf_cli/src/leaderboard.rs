@@ -0,0 +1,240 @@
+//! Rendering of private leaderboards
+use aocf::Level;
+use aocf::leaderboard::{Leaderboard, Member, Split};
+use aocf::leaderboard::global::{GlobalEntry, GlobalLeaderboard};
+use aocf::release::event_days;
+use clap::ValueEnum;
+use std::cmp::Reverse;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Order to rank members of a leaderboard in
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    /// Local score
+    Score,
+    /// Number of stars
+    Stars,
+    /// Most recent star
+    Last,
+}
+
+/// Get the members of a leaderboard in ranked order
+///
+/// Ties are broken by whoever gained their last star first, as on the site.
+pub fn ranked(board: &Leaderboard, sort: Sort) -> Vec<&Member> {
+    let mut members: Vec<_> = board.members.values().collect();
+    let first_to_finish = |m: &Member| (m.last_star_ts.is_none(), m.last_star_ts, m.id);
+    match sort {
+        Sort::Score => members.sort_by_key(|m| (Reverse(m.local_score), first_to_finish(m))),
+        Sort::Stars => members.sort_by_key(|m| (Reverse(m.stars), first_to_finish(m))),
+        Sort::Last => members.sort_by_key(|m| (Reverse(m.last_star_ts), m.id)),
+    }
+    members
+}
+
+fn sort_key(member: &Member, sort: Sort) -> i64 {
+    match sort {
+        Sort::Score => member.local_score.into(),
+        Sort::Stars => member.stars.into(),
+        Sort::Last => member.last_star_ts.map_or(0, |t| t.timestamp()),
+    }
+}
+
+/// Get a member's stars for each day, `*` for both stars, `.` for only the
+/// first, and a space for none
+fn star_grid(member: &Member, days: u32) -> String {
+    (1..=days)
+        .map(|d| match (member.star_ts(d, Level::First), member.star_ts(d, Level::Second)) {
+            (Some(_), Some(_)) => '*',
+            (Some(_), None) => '.',
+            _ => ' ',
+        })
+        .collect()
+}
+
+/// Get the header for the star grid, with day numbers written vertically
+fn day_header(days: u32) -> (String, String) {
+    let tens = (1..=days)
+        .map(|d| if d >= 10 { char::from(b'0' + (d / 10) as u8) } else { ' ' })
+        .collect();
+    let units = (1..=days)
+        .map(|d| char::from(b'0' + (d % 10) as u8))
+        .collect();
+    (tens, units)
+}
+
+/// Get ranks to show, ranks are left out for members tied with the member
+/// above
+fn ranks<'a>(members: &'a [&Member], sort: Sort) -> impl Iterator<Item = Option<usize>> + 'a {
+    members.iter()
+        .enumerate()
+        .map(move |(i, m)| match i {
+            0 => Some(1),
+            _ if sort_key(members[i - 1], sort) == sort_key(m, sort) => None,
+            _ => Some(i + 1),
+        })
+}
+
+/// Render a leaderboard as a plain text table
+pub fn render(board: &Leaderboard, sort: Sort) -> String {
+    let days = event_days(board.year).unwrap_or(25);
+    let members = ranked(board, sort);
+    let (tens, units) = day_header(days);
+    let mut out = String::new();
+
+    writeln!(out, "{:4} {:5} {:5} {}", "", "", "", tens).unwrap();
+    writeln!(out, "{:4} {:>5} {:>5} {} name", "", "score", "stars", units).unwrap();
+    for (rank, member) in ranks(&members, sort).zip(&members) {
+        let rank = rank.map(|r| format!("{})", r)).unwrap_or_default();
+        let grid = star_grid(member, days);
+        writeln!(
+            out, "{:>4} {:5} {:5} {} {}",
+            rank, member.local_score, member.stars, grid, member.display_name(),
+        ).unwrap();
+    }
+
+    out
+}
+
+/// Render a leaderboard as a markdown table, for viewing pretty
+pub fn render_markdown(board: &Leaderboard, sort: Sort) -> String {
+    let days = event_days(board.year).unwrap_or(25);
+    let members = ranked(board, sort);
+    let mut out = format!("# Private leaderboard {}\n\n", board.year);
+
+    out.push_str("|rank|score|stars|days|name|\n|-:|-:|-:|:-|:-|\n");
+    for (rank, member) in ranks(&members, sort).zip(&members) {
+        let rank = rank.map(|r| r.to_string()).unwrap_or_default();
+        let grid = star_grid(member, days).replace(' ', "-");
+        writeln!(
+            out, "|{}|{}|{}|`{}`|{}|",
+            rank, member.local_score, member.stars, grid, member.display_name(),
+        ).unwrap();
+    }
+
+    out
+}
+
+/// Get split times, for one day or all days, ordered by day and then the
+/// delta between parts, fastest first
+fn splits(board: &Leaderboard, day: Option<u32>) -> Vec<Split> {
+    let mut splits: Vec<_> = board.splits()
+        .into_iter()
+        .filter(|s| day.is_none_or(|d| s.day == d))
+        .collect();
+    splits.sort_by_key(|s| (s.day, s.delta().is_none(), s.delta(), s.first));
+    splits
+}
+
+fn member_name(board: &Leaderboard, split: &Split) -> String {
+    board.members.get(&split.member)
+        .map(Member::display_name)
+        .unwrap_or_default()
+}
+
+/// Format a split time as hours, minutes and seconds
+fn format_split(time: Option<Duration>) -> String {
+    match time {
+        Some(t) => {
+            let secs = t.as_secs();
+            format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+        },
+        None => "-".to_string(),
+    }
+}
+
+/// Render split times as a plain text table
+pub fn render_splits(board: &Leaderboard, day: Option<u32>) -> String {
+    let mut out = format!("{:>3} {:>10} {:>10} {:>10} name\n", "day", "part 1", "part 2", "delta");
+    for split in splits(board, day) {
+        writeln!(
+            out, "{:>3} {:>10} {:>10} {:>10} {}",
+            split.day,
+            format_split(Some(split.first)),
+            format_split(split.second),
+            format_split(split.delta()),
+            member_name(board, &split),
+        ).unwrap();
+    }
+    out
+}
+
+/// Render split times as a markdown table, for viewing pretty
+pub fn render_splits_markdown(board: &Leaderboard, day: Option<u32>) -> String {
+    let mut out = format!("# Private leaderboard {} splits\n\n", board.year);
+    out.push_str("|day|part 1|part 2|delta|name|\n|-:|-:|-:|-:|:-|\n");
+    for split in splits(board, day) {
+        writeln!(
+            out, "|{}|{}|{}|{}|{}|",
+            split.day,
+            format_split(Some(split.first)),
+            format_split(split.second),
+            format_split(split.delta()),
+            member_name(board, &split),
+        ).unwrap();
+    }
+    out
+}
+
+/// Render split times as CSV, with times in seconds
+pub fn render_splits_csv(board: &Leaderboard, day: Option<u32>) -> String {
+    let secs = |t: Option<Duration>| t.map(|t| t.as_secs().to_string()).unwrap_or_default();
+    let mut out = "day,member_id,name,part1,part2,delta\n".to_string();
+    for split in splits(board, day) {
+        let name = member_name(board, &split).replace('"', "\"\"");
+        writeln!(
+            out, "{},{},\"{}\",{},{},{}",
+            split.day,
+            split.member,
+            name,
+            secs(Some(split.first)),
+            secs(split.second),
+            secs(split.delta()),
+        ).unwrap();
+    }
+    out
+}
+
+fn global_sections(board: &GlobalLeaderboard) -> [(String, &[GlobalEntry]); 2] {
+    let title = |desc: &str, level| {
+        let capped = board.capped(level)
+            .map(|t| format!(", capped at {}", format_split(Some(t))))
+            .unwrap_or_default();
+        format!("{}, day {}{}", desc, board.day, capped)
+    };
+    [
+        (title("Both stars", Level::Second), &board.second),
+        (title("First star", Level::First), &board.first),
+    ]
+}
+
+/// Render a global leaderboard as plain text
+pub fn render_global(board: &GlobalLeaderboard) -> String {
+    let mut out = String::new();
+    for (title, entries) in &global_sections(board) {
+        writeln!(out, "{}:", title).unwrap();
+        for (i, entry) in entries.iter().enumerate() {
+            let tied = i > 0 && entries[i - 1].rank == entry.rank;
+            let rank = if tied { String::new() } else { format!("{})", entry.rank) };
+            let supporter = if entry.supporter { " (AoC++)" } else { "" };
+            writeln!(out, "{:>4} {:>9} {}{}", rank, format_split(Some(entry.time)), entry.user, supporter).unwrap();
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Render a global leaderboard as markdown tables, for viewing pretty
+pub fn render_global_markdown(board: &GlobalLeaderboard) -> String {
+    let mut out = format!("# Global leaderboard {}\n\n", board.year);
+    for (title, entries) in &global_sections(board) {
+        writeln!(out, "## {}\n\n|rank|time|user|AoC++|\n|-:|-:|:-|:-:|", title).unwrap();
+        for entry in entries.iter() {
+            let supporter = if entry.supporter { "*" } else { "" };
+            writeln!(out, "|{}|{}|{}|{}|", entry.rank, format_split(Some(entry.time)), entry.user, supporter).unwrap();
+        }
+        out.push('\n');
+    }
+    out
+}
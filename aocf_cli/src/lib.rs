@@ -1,6 +1,8 @@
-#[macro_use] extern crate failure;
 #[macro_use] extern crate serde_derive;
 
 pub mod conf;
 pub mod pretty;
 pub mod cli;
+pub mod exec;
+pub mod firefox;
+pub mod leaderboard;
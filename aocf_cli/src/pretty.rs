@@ -20,7 +20,7 @@ use crossterm::{
     },
 };
 use std::io::{stderr, Write};
-use failure::Error;
+use anyhow::Error;
 use termimad::{
     MadSkin, MadView, Alignment, Area, CompoundStyle,
 };
@@ -0,0 +1,80 @@
+//! Identity of the logged in account
+//!
+//! Every page on the site has a header naming the logged in user, or a link
+//! to log in, so a session cookie can be validated by fetching any page.
+use crate::examples::decode;
+use crate::http::get_body;
+use crate::{Client, Error};
+use regex::Regex;
+
+/// Page fetched to validate a session, it's small, and requires logging in
+const PATH: &str = "/settings";
+
+/// The account a session cookie is for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    /// The name of the user, as shown on the site
+    pub name: String,
+    /// Whether the user is an AoC++ supporter
+    pub supporter: bool,
+}
+
+impl Account {
+    /// Get the logged in account from a page's header, if logged in
+    pub fn from_html(html: &str) -> Option<Self> {
+        let user = Regex::new(r#"(?s)<div class="user">(.*?)</div>"#).unwrap();
+        let badge = Regex::new(r#"(?s)<a [^>]*class="supporter-badge"[^>]*>.*?</a>"#).unwrap();
+        let stars = Regex::new(r#"(?s)<span class="star-count">.*?</span>"#).unwrap();
+
+        let html = &user.captures(html)?[1];
+        let supporter = badge.is_match(html);
+        let name = badge.replace_all(html, "");
+        let name = decode(&stars.replace_all(&name, "")).trim().to_string();
+
+        match name.is_empty() {
+            true => None,
+            false => Some(Self { name, supporter }),
+        }
+    }
+}
+
+fn parse_response(body: &str) -> Result<Account, Error> {
+    Account::from_html(body).ok_or(Error::Unauthenticated)
+}
+
+/// Validate the client's session cookie, getting the account it's for
+///
+/// Fails with [`Error::Unauthenticated`] if the cookie isn't valid, or has
+/// expired.
+pub fn whoami(client: &Client) -> Result<Account, Error> {
+    let resp = client.get(PATH)?;
+    parse_response(&get_body(resp, PATH)?)
+}
+
+/// Validate the client's session cookie asynchronously, see [`whoami`]
+#[cfg(feature = "async")]
+pub async fn whoami_async(client: &Client) -> Result<Account, Error> {
+    let resp = client.get_async(PATH).await?;
+    parse_response(&get_body(resp, PATH)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockTransport;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_whoami() {
+        let page = r#"<header><div><h1 class="title-global"><a href="/">Advent of Code</a></h1><nav><ul><li><a href="/2020/about">[About]</a></li></ul></nav><div class="user">some &amp; one <a href="/2020/support" class="supporter-badge" title="Advent of Code Supporter">(AoC++)</a> <span class="star-count">50*</span></div></div></header>"#;
+        let mock = Arc::new(MockTransport::new().route("/settings", 200, page));
+        let client = Client::new("secret").throttle(None).transport(mock);
+        let account = whoami(&client).unwrap();
+        assert_eq!(account, Account { name: "some & one".into(), supporter: true });
+
+        let login = r#"<header><nav><ul><li><a href="/2020/auth/login">[Log In]</a></li></ul></nav></header>"#;
+        let mock = Arc::new(MockTransport::new().route("/settings", 200, login));
+        let client = Client::new("secret").throttle(None).transport(mock);
+        assert!(matches!(whoami(&client), Err(Error::Unauthenticated)));
+    }
+}
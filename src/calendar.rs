@@ -0,0 +1,100 @@
+//! Parsing of the calendar for a year
+//!
+//! The calendar at `/{year}` shows the stars gained for every day, including
+//! those solved on the website rather than with aocf.
+use crate::http::get_body;
+use crate::{Client, Error};
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// The calendar for a year's event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Calendar {
+    pub year: i32,
+    /// Stars gained for each day which has been released
+    pub days: BTreeMap<u32, u8>,
+}
+
+impl Calendar {
+    /// Parse the calendar from the year's page
+    ///
+    /// Days with the `calendar-verycomplete` class have both stars, and days
+    /// with the `calendar-complete` class have one. Only released days are
+    /// links.
+    pub fn from_html(html: &str, year: i32) -> Self {
+        let day = Regex::new(r#"<a [^>]*class="calendar-day(\d+)([^"]*)""#).unwrap();
+        let days = day.captures_iter(html)
+            .filter_map(|c| {
+                let day = c[1].parse().ok()?;
+                let classes: Vec<_> = c[2].split_whitespace().collect();
+                let stars = if classes.contains(&"calendar-verycomplete") {
+                    2
+                } else if classes.contains(&"calendar-complete") {
+                    1
+                } else {
+                    0
+                };
+                Some((day, stars))
+            })
+            .collect();
+        Self { year, days }
+    }
+
+    /// Get the stars gained for a day
+    pub fn stars(&self, day: u32) -> u8 {
+        self.days.get(&day).copied().unwrap_or_default()
+    }
+
+    /// Get the total stars gained in the year
+    pub fn total_stars(&self) -> u32 {
+        self.days.values().map(|s| u32::from(*s)).sum()
+    }
+}
+
+fn parse_response(body: &str, year: i32) -> Result<Calendar, Error> {
+    // the calendar is shown without stars when not logged in
+    if body.contains("[Log In]") {
+        return Err(Error::Unauthenticated);
+    }
+    Ok(Calendar::from_html(body, year))
+}
+
+/// Fetch the calendar for a year
+pub fn fetch(client: &Client, year: i32) -> Result<Calendar, Error> {
+    let path = format!("/{}", year);
+    let resp = client.get(&path)?;
+    parse_response(&get_body(resp, &path)?, year)
+}
+
+/// Fetch the calendar for a year asynchronously
+#[cfg(feature = "async")]
+pub async fn fetch_async(client: &Client, year: i32) -> Result<Calendar, Error> {
+    let path = format!("/{}", year);
+    let resp = client.get_async(&path).await?;
+    parse_response(&get_body(resp, &path)?, year)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<main>
+<pre class="calendar">
+<a aria-label="Day 1, two stars" href="/2020/day/1" class="calendar-day1 calendar-verycomplete">  <span class="calendar-mark-complete">*</span><span class="calendar-mark-verycomplete">*</span></a>
+<a aria-label="Day 2, one star" href="/2020/day/2" class="calendar-day2 calendar-complete">  <span class="calendar-mark-complete">*</span></a>
+<a aria-label="Day 3" href="/2020/day/3" class="calendar-day3">  <span class="calendar-day"> 3</span></a>
+<span aria-hidden="true" class="calendar-day4">  <span class="calendar-day"> 4</span></span>
+</pre>
+</main>"#;
+
+    #[test]
+    fn test_parse_calendar() {
+        let calendar = Calendar::from_html(PAGE, 2020);
+        assert_eq!(calendar.days.len(), 3);
+        assert_eq!(calendar.stars(1), 2);
+        assert_eq!(calendar.stars(2), 1);
+        assert_eq!(calendar.stars(3), 0);
+        assert_eq!(calendar.stars(4), 0);
+        assert_eq!(calendar.total_stars(), 3);
+    }
+}
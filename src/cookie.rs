@@ -1,35 +1,84 @@
+// diesel's derive and table macros expand to impls nested in constants
+#![allow(non_local_definitions)]
+
+mod chromium;
 mod db_models;
 mod db_schema;
 
+use chrono::{DateTime, TimeZone, Utc};
 use diesel::prelude::*;
 use diesel::sqlite::SqliteConnection;
-use failure::{Error, bail};
+use crate::Error;
 use std::path::Path;
 
 use db_models::*;
 use db_schema::*;
 
+pub use chromium::get_chromium_session_cookie;
+
 fn connect_sqlite(path: impl AsRef<Path>) -> Result<SqliteConnection, Error> {
     let path = match path.as_ref().to_str() {
         Some(p) => p,
-        None => bail!("can't parse path to string"),
+        None => return Err(Error::CookieStore("can't parse path to string".to_string())),
     };
-    let connection = SqliteConnection::establish(path)?;
+    let connection = SqliteConnection::establish(path)
+        .map_err(|e| Error::CookieStore(e.to_string()))?;
     Ok(connection)
 }
 
-pub fn get_session_cookie(path: impl AsRef<Path>) -> Result<String, Error> {
+/// Expiry times greater than this are in milliseconds, as stored by newer
+/// versions of Firefox, rather than seconds
+const EXPIRY_MILLIS_FROM: i64 = 100_000_000_000;
+
+fn find_session_cookie(path: impl AsRef<Path>) -> Result<FirefoxCookie, Error> {
     let connection = connect_sqlite(path)?;
 
-    let records = moz_cookies::table
+    moz_cookies::table
         .filter(moz_cookies::host.eq(".adventofcode.com"))
         .filter(moz_cookies::name.eq("session"))
-        .limit(1)
-        .load::<FirefoxCookie>(&connection)?;
+        .first::<FirefoxCookie>(&connection)
+        .optional()
+        .map_err(|e| Error::CookieStore(e.to_string()))?
+        .ok_or_else(|| Error::CookieStore("no cookie found in the cookie store".to_string()))
+}
+
+pub fn get_session_cookie(path: impl AsRef<Path>) -> Result<String, Error> {
+    Ok(find_session_cookie(path)?.value)
+}
+
+/// Get when the session cookie in a Firefox cookie store expires
+pub fn get_session_cookie_expiry(path: impl AsRef<Path>) -> Result<DateTime<Utc>, Error> {
+    let expiry = find_session_cookie(path)?.expiry;
+    let expiry = match expiry >= EXPIRY_MILLIS_FROM {
+        true => Utc.timestamp_millis_opt(expiry),
+        false => Utc.timestamp_opt(expiry, 0),
+    };
+    expiry.single()
+        .ok_or_else(|| Error::CookieStore("invalid cookie expiry time".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::connection::SimpleConnection;
+
+    #[test]
+    fn test_get_session_cookie() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("cookies.sqlite");
+        connect_sqlite(&path).unwrap().batch_execute("
+            CREATE TABLE moz_cookies (id INTEGER PRIMARY KEY, originAttributes TEXT NOT NULL DEFAULT '', name TEXT, value TEXT, host TEXT, path TEXT, expiry INTEGER, lastAccessed INTEGER, creationTime INTEGER, isSecure INTEGER, isHttpOnly INTEGER, inBrowserElement INTEGER DEFAULT 0, sameSite INTEGER DEFAULT 0);
+            INSERT INTO moz_cookies VALUES (1, '', 'session', 'other', '.example.com', '/', 1, 0, 0, 1, 1, 0, 0);
+            INSERT INTO moz_cookies VALUES (2, '', 'session', '53616c746564', '.adventofcode.com', '/', 1700000000, 0, 0, 1, 1, 0, 0);
+        ").unwrap();
+
+        assert_eq!(get_session_cookie(&path).unwrap(), "53616c746564");
+        let expiry = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        assert_eq!(get_session_cookie_expiry(&path).unwrap(), expiry);
 
-    if records.is_empty() {
-        bail!("no cookie found in the cookie store");
-    } else {
-        Ok(records[0].value.to_owned())
+        connect_sqlite(&path).unwrap()
+            .batch_execute("UPDATE moz_cookies SET expiry = 1700000000000 WHERE id = 2")
+            .unwrap();
+        assert_eq!(get_session_cookie_expiry(&path).unwrap(), expiry);
     }
 }
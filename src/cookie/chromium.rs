@@ -0,0 +1,139 @@
+//! Chromium's cookie store
+//!
+//! Cookie values are encrypted with AES-128-CBC, with a key derived from a
+//! password using PBKDF2. On Linux, values prefixed with `v10` use the fixed
+//! password "peanuts", and values prefixed with `v11` use a secret stored in
+//! the keyring, e.g. as given by `secret-tool lookup application chromium`.
+use super::db_models::ChromiumCookie;
+use super::db_schema::{cookies, meta};
+use super::connect_sqlite;
+use crate::{Error, hex};
+use aes::Aes128;
+use cbc::cipher::{BlockDecryptMut, KeyIvInit, block_padding::Pkcs7};
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use sha1::Sha1;
+use std::path::Path;
+
+/// Password for values encrypted without a keyring
+const V10_PASSWORD: &str = "peanuts";
+const SALT: &[u8] = b"saltysalt";
+const IV: [u8; 16] = [b' '; 16];
+/// Database version from which values are prefixed with a SHA-256 hash of the
+/// cookie's domain
+const HASH_PREFIX_VERSION: i64 = 24;
+const HASH_PREFIX_LEN: usize = 32;
+
+/// Get the session cookie from a Chromium cookie store
+///
+/// The keyring secret is needed for cookies encrypted with it, which is the
+/// case when Chromium has access to a keyring. The store may be an offline
+/// copy.
+pub fn get_chromium_session_cookie(path: impl AsRef<Path>, keyring_secret: Option<&str>) -> Result<String, Error> {
+    let connection = connect_sqlite(path)?;
+    let store_error = |e: diesel::result::Error| Error::CookieStore(e.to_string());
+
+    let records = cookies::table
+        .select((cookies::value, sql::<Text>("hex(encrypted_value)")))
+        .filter(cookies::host_key.eq(".adventofcode.com"))
+        .filter(cookies::name.eq("session"))
+        .limit(1)
+        .load::<ChromiumCookie>(&connection)
+        .map_err(store_error)?;
+
+    let version = meta::table
+        .select(meta::value)
+        .filter(meta::key.eq("version"))
+        .first::<String>(&connection)
+        .optional()
+        .map_err(store_error)?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_default();
+
+    match records.first() {
+        None => Err(Error::CookieStore("no cookie found in the cookie store".to_string())),
+        Some(c) if c.encrypted_value.is_empty() => Ok(c.value.clone()),
+        Some(c) => {
+            let encrypted = hex::decode(&c.encrypted_value)
+                .ok_or_else(|| Error::CookieStore("invalid encrypted cookie value".to_string()))?;
+            decrypt(&encrypted, keyring_secret, version)
+        },
+    }
+}
+
+/// Decrypt a cookie value
+fn decrypt(encrypted: &[u8], keyring_secret: Option<&str>, version: i64) -> Result<String, Error> {
+    let (prefix, data) = encrypted.split_at(encrypted.len().min(3));
+    let password = match (prefix, keyring_secret) {
+        (b"v10", _) => V10_PASSWORD,
+        (b"v11", Some(secret)) => secret,
+        (b"v11", None) => {
+            let message = "cookie is encrypted with a keyring secret, which wasn't given";
+            return Err(Error::CookieStore(message.to_string()));
+        },
+        _ => return Err(Error::CookieStore("unsupported cookie encryption".to_string())),
+    };
+
+    let mut key = [0; 16];
+    pbkdf2::pbkdf2_hmac::<Sha1>(password.as_bytes(), SALT, 1, &mut key);
+
+    let decrypted = cbc::Decryptor::<Aes128>::new(&key.into(), &IV.into())
+        .decrypt_padded_vec_mut::<Pkcs7>(data)
+        .map_err(|_| Error::CookieStore("couldn't decrypt cookie, the keyring secret may be wrong".to_string()))?;
+
+    let value = match version {
+        v if v >= HASH_PREFIX_VERSION => decrypted.get(HASH_PREFIX_LEN..).unwrap_or_default(),
+        _ => &decrypted,
+    };
+
+    String::from_utf8(value.to_vec())
+        .map_err(|_| Error::CookieStore("decrypted cookie isn't valid UTF-8".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cbc::cipher::BlockEncryptMut;
+
+    fn encrypt(prefix: &str, password: &str, value: &[u8]) -> Vec<u8> {
+        let mut key = [0; 16];
+        pbkdf2::pbkdf2_hmac::<Sha1>(password.as_bytes(), SALT, 1, &mut key);
+        let encrypted = cbc::Encryptor::<Aes128>::new(&key.into(), &IV.into())
+            .encrypt_padded_vec_mut::<Pkcs7>(value);
+        [prefix.as_bytes(), &encrypted].concat()
+    }
+
+    #[test]
+    fn test_decrypt() {
+        let v10 = encrypt("v10", "peanuts", b"53616c746564");
+        assert_eq!(decrypt(&v10, None, 0).unwrap(), "53616c746564");
+
+        let v11 = encrypt("v11", "secret", b"53616c746564");
+        assert_eq!(decrypt(&v11, Some("secret"), 0).unwrap(), "53616c746564");
+        assert!(decrypt(&v11, None, 0).is_err());
+        assert!(decrypt(&v11, Some("wrong"), 0).is_err());
+
+        let hashed = [&[0; 32][..], b"53616c746564"].concat();
+        let v10 = encrypt("v10", "peanuts", &hashed);
+        assert_eq!(decrypt(&v10, None, 24).unwrap(), "53616c746564");
+    }
+
+    #[test]
+    fn test_get_chromium_session_cookie() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("Cookies");
+        let connection = connect_sqlite(&path).unwrap();
+        let hex = hex::encode(&encrypt("v10", "peanuts", b"53616c746564"));
+        for statement in &[
+            "CREATE TABLE meta(key LONGVARCHAR NOT NULL UNIQUE PRIMARY KEY, value LONGVARCHAR)".to_string(),
+            "INSERT INTO meta VALUES('version', '23')".to_string(),
+            "CREATE TABLE cookies(creation_utc INTEGER NOT NULL, host_key TEXT NOT NULL, name TEXT NOT NULL, value TEXT NOT NULL, encrypted_value BLOB NOT NULL)".to_string(),
+            format!("INSERT INTO cookies VALUES(1, '.adventofcode.com', 'session', '', x'{}')", hex),
+        ] {
+            diesel::sql_query(statement.as_str()).execute(&connection).unwrap();
+        }
+
+        assert_eq!(get_chromium_session_cookie(&path, None).unwrap(), "53616c746564");
+    }
+}
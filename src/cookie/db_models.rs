@@ -15,7 +15,7 @@ PRAGMA table_info(moz_cookies)
 13|sameSite|INTEGER|0|0|0
 */
 
-#[allow(non_snake_case)]
+#[allow(non_snake_case, dead_code)]
 #[derive(Queryable, Debug)]
 pub struct FirefoxCookie {
     pub id: i32,
@@ -24,7 +24,7 @@ pub struct FirefoxCookie {
     pub value: String,
     pub host: String,
     pub path: String,
-    pub expiry: i32,
+    pub expiry: i64,
     pub lastAccessed: i32,
     pub creationTime: i32,
     pub isSecure: i32,
@@ -32,3 +32,10 @@ pub struct FirefoxCookie {
     pub inBrowserElement: i32,
     pub sameSite: i32,
 }
+
+#[derive(Queryable, Debug)]
+pub struct ChromiumCookie {
+    pub value: String,
+    /// Hex encoded, as diesel can't read empty blobs
+    pub encrypted_value: String,
+}
@@ -6,7 +6,7 @@ table! {
         value -> Text,
         host -> Text,
         path -> Text,
-        expiry -> Integer,
+        expiry -> BigInt,
         lastAccessed -> Integer,
         creationTime -> Integer,
         isSecure -> Integer,
@@ -15,3 +15,20 @@ table! {
         sameSite -> Integer,
     }
 }
+
+// Chromium's cookie store, only the columns which are used
+table! {
+    cookies (creation_utc) {
+        creation_utc -> BigInt,
+        host_key -> Text,
+        name -> Text,
+        value -> Text,
+    }
+}
+
+table! {
+    meta (key) {
+        key -> Text,
+        value -> Text,
+    }
+}
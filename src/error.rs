@@ -0,0 +1,58 @@
+use std::io;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Errors returned by the `aocf` crate
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Configuration is invalid
+    #[error("{0}")]
+    Config(String),
+    /// The year or day of the puzzle hasn't been set
+    #[error("day or year not set")]
+    DayOrYearNotSet,
+    /// No aocf configuration was found in the current directory or its
+    /// ancestors
+    #[error("no configuration found, maybe you need to run `aocf init`")]
+    NoConfiguration,
+    /// The session cookie is missing, invalid or expired
+    #[error("not logged in, the session cookie may be invalid or expired")]
+    Unauthenticated,
+    /// A session cookie couldn't be read from a browser's cookie store
+    #[error("cookie store: {0}")]
+    CookieStore(String),
+    /// The session cookie couldn't be stored or retrieved securely
+    #[error("secret storage: {0}")]
+    Secret(String),
+    /// A request couldn't be made
+    #[error("network error: {0}")]
+    Network(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// The server gave an error, or an unexpected response
+    #[error("server error (status {status}): {message}")]
+    Server { status: u16, message: String },
+    /// Data couldn't be parsed
+    #[error("parse error: {0}")]
+    Parse(String),
+    /// Reading or writing cached data, or other files, failed
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// The puzzle hasn't been released yet
+    #[error("puzzle not yet unlocked")]
+    NotYetUnlocked,
+    /// An answer was submitted before the wait imposed after a previous
+    /// submission has passed
+    #[error("answer submitted too recently, {}s left to wait", .remaining.as_secs())]
+    Cooldown { remaining: Duration },
+    /// An answer is known to be wrong from previous submissions
+    #[error("{0}, not submitting")]
+    KnownWrongAnswer(String),
+    /// Both levels of the puzzle have already been reached
+    #[error("already on part 2")]
+    LastLevel,
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Parse(e.to_string())
+    }
+}
@@ -0,0 +1,89 @@
+//! Parsing of the list of events
+//!
+//! The page at `/events` lists every year's event, with the stars gained in
+//! each.
+use crate::http::get_body;
+use crate::{Client, Error};
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// Stars gained in every year's event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Events {
+    /// Stars gained in each year, for every year listed
+    pub years: BTreeMap<i32, u32>,
+}
+
+impl Events {
+    /// Parse the events from the events page
+    ///
+    /// Years without any stars are listed without a star count.
+    pub fn from_html(html: &str) -> Self {
+        let event = Regex::new(r#"(?s)<div class="eventlist-event">(.*?)</div>"#).unwrap();
+        let year = Regex::new(r#"<a href="/(\d+)">"#).unwrap();
+        let stars = Regex::new(r#"<span class="star-count">\s*(\d+)\*"#).unwrap();
+
+        let years = event.captures_iter(html)
+            .filter_map(|c| {
+                let year = year.captures(&c[1])?[1].parse().ok()?;
+                let stars = stars.captures(&c[1])
+                    .and_then(|s| s[1].parse().ok())
+                    .unwrap_or_default();
+                Some((year, stars))
+            })
+            .collect();
+
+        Self { years }
+    }
+
+    /// Get the total stars gained across all years
+    pub fn total_stars(&self) -> u32 {
+        self.years.values().sum()
+    }
+}
+
+fn parse_response(body: &str) -> Result<Events, Error> {
+    // the events are listed without stars when not logged in
+    if body.contains("[Log In]") {
+        return Err(Error::Unauthenticated);
+    }
+    Ok(Events::from_html(body))
+}
+
+/// Fetch the stars gained in every year's event
+pub fn fetch(client: &Client) -> Result<Events, Error> {
+    let path = "/events";
+    let resp = client.get(path)?;
+    parse_response(&get_body(resp, path)?)
+}
+
+/// Fetch the stars gained in every year's event asynchronously
+#[cfg(feature = "async")]
+pub async fn fetch_async(client: &Client) -> Result<Events, Error> {
+    let path = "/events";
+    let resp = client.get_async(path).await?;
+    parse_response(&get_body(resp, path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<main>
+<article><p>Here are the events you can view:</p></article>
+<div class="eventlist-event"><a href="/2021">[2021]</a></div>
+<div class="eventlist-event"><a href="/2020">[2020]</a> <span class="star-count">50*</span></div>
+<div class="eventlist-event"><a href="/2019">[2019]</a> <span class="star-count">7*</span></div>
+<p>Total stars: <span class="star-count">57*</span></p>
+</main>"#;
+
+    #[test]
+    fn test_parse_events() {
+        let events = Events::from_html(PAGE);
+        assert_eq!(events.years.len(), 3);
+        assert_eq!(events.years[&2021], 0);
+        assert_eq!(events.years[&2020], 50);
+        assert_eq!(events.years[&2019], 7);
+        assert_eq!(events.total_stars(), 57);
+    }
+}
@@ -0,0 +1,114 @@
+//! Extraction of worked examples from the problem brief
+//!
+//! Examples are cached in every build, only their extraction needs the
+//! `html_parsing` feature.
+#[cfg(feature = "html_parsing")]
+use regex::Regex;
+
+/// An example input from the problem brief, with its expected answer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Example {
+    pub input: String,
+    /// The expected answer, if one was found following the example
+    pub answer: Option<String>,
+}
+
+/// Extract the examples for each part of the brief, from the brief's HTML
+///
+/// Each `<pre><code>` block is paired with the last emphasised
+/// `<code><em>` which follows it, before the next block. If a part has no
+/// example blocks of its own, as is often the case for part two, the first
+/// example of the previous part is paired with the last emphasised answer in
+/// the part.
+#[cfg(feature = "html_parsing")]
+pub fn parse_examples(html: &str) -> Vec<Vec<Example>> {
+    let article = Regex::new(r#"(?s)<article class="day-desc">(.*?)</article>"#).unwrap();
+    let block = Regex::new(r"(?s)<pre><code>(.*?)</code></pre>").unwrap();
+    let answer = Regex::new(r"(?s)<code><em>(.*?)</em></code>|<em><code>(.*?)</code></em>").unwrap();
+
+    let last_answer = |text: &str| {
+        answer.captures_iter(text)
+            .last()
+            .and_then(|c| c.get(1).or_else(|| c.get(2)))
+            .map(|m| decode(m.as_str()).trim().to_string())
+    };
+
+    let mut parts: Vec<Vec<Example>> = vec![];
+
+    for article in article.captures_iter(html).filter_map(|c| c.get(1)) {
+        let article = article.as_str();
+        let blocks: Vec<_> = block.captures_iter(article)
+            .filter_map(|c| Some((c.get(0)?.end(), c.get(1)?.as_str())))
+            .collect();
+
+        let examples = if blocks.is_empty() {
+            parts.last()
+                .and_then(|p| p.first())
+                .map(|e| Example { input: e.input.clone(), answer: last_answer(article) })
+                .into_iter()
+                .collect()
+        } else {
+            let starts = block.find_iter(article).skip(1).map(|m| m.start());
+            let ends = starts.chain(std::iter::once(article.len()));
+            blocks.iter()
+                .zip(ends)
+                .map(|((end, input), next)| Example {
+                    input: decode(input),
+                    answer: last_answer(&article[*end..next]),
+                })
+                .collect()
+        };
+
+        parts.push(examples);
+    }
+
+    parts
+}
+
+/// Strip tags and decode HTML entities from an HTML fragment
+#[cfg(feature = "html_parsing")]
+pub(crate) fn decode(html: &str) -> String {
+    let tags = Regex::new(r"<[^>]*>").unwrap();
+    tags.replace_all(html, "")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(all(test, feature = "html_parsing"))]
+mod tests {
+    use super::*;
+
+    const BRIEF: &str = r#"<main>
+<article class="day-desc"><h2>--- Day 1: Test ---</h2><p>For example:</p>
+<pre><code>1
+2
+&lt;3&gt;
+</code></pre>
+<p>Here, the sum is <code>1 + 2</code> giving <code><em>6</em></code>.</p>
+<p>Another example:</p>
+<pre><code>4 <em>5</em>
+</code></pre>
+<p>This one has no answer.</p>
+</article>
+<p>Your puzzle answer was <code>7</code>.</p>
+<article class="day-desc"><h2 id="part2">--- Part Two ---</h2>
+<p>Now the answer for the example above is <code><em>-1</em></code>.</p>
+</article>
+</main>"#;
+
+    #[test]
+    fn test_parse_examples() {
+        let parts = parse_examples(BRIEF);
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0], vec![
+            Example { input: "1\n2\n<3>\n".into(), answer: Some("6".into()) },
+            Example { input: "4 5\n".into(), answer: None },
+        ]);
+        assert_eq!(parts[1], vec![
+            Example { input: "1\n2\n<3>\n".into(), answer: Some("-1".into()) },
+        ]);
+    }
+}
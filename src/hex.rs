@@ -0,0 +1,27 @@
+//! Hex encoding, for binary data kept in text
+/// Encode bytes as lowercase hex
+#[cfg(any(test, feature = "encrypted_cookie"))]
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode hex, in either case, failing if it isn't valid
+pub(crate) fn decode(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(encode(&[0, 0xab, 0x10]), "00ab10");
+        assert_eq!(decode("00AB10"), Some(vec![0, 0xab, 0x10]));
+        assert_eq!(decode("00a"), None);
+        assert_eq!(decode("0g"), None);
+    }
+}
@@ -1,14 +1,22 @@
 use crate::Aoc;
-use failure::{Error, bail};
+use crate::throttle::Throttle;
+use crate::transport::{Method, Request, Response, Transport, UreqTransport};
+use crate::Error;
+use std::sync::Arc;
+
+#[cfg(feature = "async")]
+use crate::transport::{AsyncTransport, ReqwestTransport};
 
 #[cfg(feature = "html_parsing")]
 use crate::Level;
 #[cfg(feature = "html_parsing")]
+use crate::examples::{Example, parse_examples};
+#[cfg(feature = "html_parsing")]
 use html2md::parse_html;
 #[cfg(feature = "html_parsing")]
 use regex::Regex;
 
-const BASE: &str = "https://adventofcode.com";
+pub const BASE: &str = "https://adventofcode.com";
 
 fn user_agent() -> String {
     let repo = env!("CARGO_PKG_REPOSITORY");
@@ -18,74 +26,319 @@ fn user_agent() -> String {
     format!("{}@{} by {}", repo, version, authors)
 }
 
-fn get_url(aoc: &Aoc) -> Result<String, Error> {
-    let url = match (aoc.day, aoc.year) {
-        (Some(d), Some(y)) => format!("{}/{}/day/{}", BASE, y, d),
-        _ => bail!("day or year not set"),
+/// Client for making authenticated requests to Advent of Code
+#[derive(Debug, Clone)]
+pub struct Client {
+    base_url: String,
+    cookie: String,
+    transport: Arc<dyn Transport>,
+    #[cfg(feature = "async")]
+    async_transport: Arc<dyn AsyncTransport>,
+    throttle: Option<Throttle>,
+}
+
+impl Client {
+    /// Create a client using the given session cookie
+    pub fn new(cookie: &str) -> Self {
+        Self {
+            base_url: BASE.to_string(),
+            cookie: cookie.to_string(),
+            transport: Arc::new(UreqTransport),
+            #[cfg(feature = "async")]
+            async_transport: Arc::new(ReqwestTransport::default()),
+            throttle: Some(Throttle::default()),
+        }
+    }
+
+    /// Set the base URL, in place of `https://adventofcode.com`
+    pub fn base_url(mut self, url: &str) -> Self {
+        self.base_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the transport used to make requests
+    pub fn transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Set the transport used to make asynchronous requests
+    #[cfg(feature = "async")]
+    pub fn async_transport(mut self, transport: Arc<dyn AsyncTransport>) -> Self {
+        self.async_transport = transport;
+        self
+    }
+
+    /// Set the throttle enforcing a minimum interval between requests, or
+    /// disable throttling
+    pub fn throttle(mut self, throttle: Option<Throttle>) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    /// Send a request, after waiting for the throttle
+    fn send(&self, request: &Request) -> Result<Response, Error> {
+        if let Some(throttle) = &self.throttle {
+            throttle.wait()?;
+        }
+        self.transport.send(request)
+    }
+
+    /// Send a request asynchronously, after waiting for the throttle
+    #[cfg(feature = "async")]
+    async fn send_async(&self, request: &Request) -> Result<Response, Error> {
+        if let Some(throttle) = &self.throttle {
+            throttle.wait_async().await?;
+        }
+        self.async_transport.send_async(request).await
+    }
+
+    fn request(&self, method: Method, path: &str) -> Request {
+        Request {
+            method,
+            url: format!("{}{}", self.base_url, path),
+            headers: vec![
+                ("Cookie".to_string(), format!("session={}", self.cookie)),
+                ("User-Agent".to_string(), user_agent()),
+            ],
+            form: vec![],
+        }
+    }
+
+    fn get_request(&self, path: &str, headers: &[(&str, &str)]) -> Request {
+        let mut request = self.request(Method::Get, path);
+        request.headers.extend(headers.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        request
+    }
+
+    fn form_request(&self, path: &str, form: &[(&str, &str)]) -> Request {
+        let mut request = self.request(Method::Post, path);
+        request.form = form.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        request
+    }
+
+    /// Make a GET request for a path
+    pub fn get(&self, path: &str) -> Result<Response, Error> {
+        self.get_with_headers(path, &[])
+    }
+
+    /// Make a GET request for a path, with additional headers
+    pub fn get_with_headers(&self, path: &str, headers: &[(&str, &str)]) -> Result<Response, Error> {
+        self.send(&self.get_request(path, headers))
+    }
+
+    /// Make a POST request for a path, sending form data
+    pub fn post_form(&self, path: &str, form: &[(&str, &str)]) -> Result<Response, Error> {
+        self.send(&self.form_request(path, form))
+    }
+
+    /// Make a GET request for a path asynchronously
+    #[cfg(feature = "async")]
+    pub async fn get_async(&self, path: &str) -> Result<Response, Error> {
+        self.get_with_headers_async(path, &[]).await
+    }
+
+    /// Make a GET request for a path asynchronously, with additional headers
+    #[cfg(feature = "async")]
+    pub async fn get_with_headers_async(&self, path: &str, headers: &[(&str, &str)]) -> Result<Response, Error> {
+        self.send_async(&self.get_request(path, headers)).await
+    }
+
+    /// Make a POST request for a path asynchronously, sending form data
+    #[cfg(feature = "async")]
+    pub async fn post_form_async(&self, path: &str, form: &[(&str, &str)]) -> Result<Response, Error> {
+        self.send_async(&self.form_request(path, form)).await
+    }
+}
+
+fn get_path(aoc: &Aoc) -> Result<String, Error> {
+    let path = match (aoc.day, aoc.year) {
+        (Some(d), Some(y)) => format!("/{}/day/{}", y, d),
+        _ => return Err(Error::DayOrYearNotSet),
     };
-    Ok(url)
+    Ok(path)
 }
 
-fn get_content(aoc: &Aoc, suffix: &str) -> Result<String, Error> {
-    let url = format!("{}{}", get_url(aoc)?, suffix);
-    let cookie = format!("session={}", aoc.cookie);
-    let user_agent = user_agent();
+/// Identify an error from the body of a response
+fn error_from_body(body: &str) -> Option<Error> {
+    if body.contains("before it unlocks") {
+        Some(Error::NotYetUnlocked)
+    } else if body.contains("Please log in") || body.contains("please identify yourself") {
+        Some(Error::Unauthenticated)
+    } else {
+        None
+    }
+}
 
-    let input = ureq::get(&url)
-        .set("COOKIE", &cookie)
-        .set("User-Agent", &user_agent)
-        .call()?
-        .into_string()?;
+/// Check whether some text, such as cached input data, is actually an error
+/// page from the server
+pub fn is_error_page(text: &str) -> bool {
+    error_from_body(text).is_some() || is_html(text)
+}
 
-    Ok(input)
+/// Check whether text is an HTML document, puzzle input never is
+pub(crate) fn is_html(text: &str) -> bool {
+    let start = text.trim_start().get(..14).unwrap_or_default().to_ascii_lowercase();
+    start.starts_with("<!doctype html") || start.starts_with("<html")
+}
+
+/// Check a response was successful, and get its body
+pub(crate) fn get_body(resp: Response, path: &str) -> Result<String, Error> {
+    if let Some(e) = error_from_body(&resp.body) {
+        return Err(e);
+    }
+    match resp.status {
+        401 | 403 => Err(Error::Unauthenticated),
+        s if !resp.is_success() => {
+            let message = format!("{}: {}", path, resp.body.lines().next().unwrap_or_default());
+            Err(Error::Server { status: s, message })
+        },
+        _ => Ok(resp.body),
+    }
+}
+
+/// Validators identifying the version of a page, used to make conditional
+/// requests for it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+#[cfg(feature = "html_parsing")]
+impl Validators {
+    fn from_response(resp: &Response) -> Option<Self> {
+        let validators = Self {
+            etag: resp.header("ETag").map(str::to_string),
+            last_modified: resp.header("Last-Modified").map(str::to_string),
+        };
+        if validators == Self::default() { None } else { Some(validators) }
+    }
+
+    fn headers(&self) -> Vec<(&str, &str)> {
+        let etag = self.etag.as_deref().map(|v| ("If-None-Match", v));
+        let modified = self.last_modified.as_deref().map(|v| ("If-Modified-Since", v));
+        etag.into_iter().chain(modified).collect()
+    }
+}
+
+/// A problem brief, parsed from the problem's page
+#[cfg(feature = "html_parsing")]
+#[derive(Debug)]
+pub struct Brief {
+    pub title: String,
+    /// The brief converted to markdown
+    pub brief: String,
+    /// Examples from the brief, for each part
+    pub examples: Vec<Vec<Example>>,
+    pub validators: Option<Validators>,
+}
+
+/// Get the problem brief
+///
+/// If validators from a previous response are given, the request is made
+/// conditionally, and `None` is returned if the page hasn't been modified.
+#[cfg(feature = "html_parsing")]
+pub fn get_brief(aoc: &Aoc, validators: Option<&Validators>) -> Result<Option<Brief>, Error> {
+    let path = get_path(aoc)?;
+    let headers = validators.map(Validators::headers).unwrap_or_default();
+    let resp = aoc.client().get_with_headers(&path, &headers)?;
+    parse_brief(resp, &path)
+}
+
+/// Get the problem brief asynchronously, see [`get_brief`]
+#[cfg(all(feature = "html_parsing", feature = "async"))]
+pub async fn get_brief_async(aoc: &Aoc, validators: Option<&Validators>) -> Result<Option<Brief>, Error> {
+    let path = get_path(aoc)?;
+    let headers = validators.map(Validators::headers).unwrap_or_default();
+    let resp = aoc.client().get_with_headers_async(&path, &headers).await?;
+    parse_brief(resp, &path)
 }
 
 #[cfg(feature = "html_parsing")]
-pub fn get_brief(aoc: &Aoc) -> Result<(String, String), Error> {
-    let brief = get_content(aoc, "")?;
+fn parse_brief(resp: Response, path: &str) -> Result<Option<Brief>, Error> {
+    if resp.status == 304 {
+        return Ok(None);
+    }
+    let validators = Validators::from_response(&resp);
+    let status = resp.status;
+    let brief = get_body(resp, path)?;
+
     let title = get_title(&brief).unwrap_or_default();
-    let brief = get_html_section(&brief, "main").unwrap_or_default();
+    let examples = parse_examples(&brief);
+    // e.g. a maintenance page
+    let brief = get_html_section(&brief, "main").ok_or_else(|| {
+        let message = format!("{}: no puzzle brief found in the response", path);
+        Error::Server { status, message }
+    })?;
     let brief = parse_html(&brief);
     let num_lines = brief.lines().count();
     let brief = brief.lines()
         .skip(2)
-        .take(num_lines - 4)
+        .take(num_lines.saturating_sub(4))
         .map(|l| format!("{}\n", l))
         .collect::<String>()
         .trim()
         .to_string();
-    Ok((title, brief))
+    Ok(Some(Brief { title, brief, examples, validators }))
 }
 
 pub fn get_input(aoc: &Aoc) -> Result<String, Error> {
-    let input = get_content(aoc, "/input")?;
+    let path = format!("{}/input", get_path(aoc)?);
+    let resp = aoc.client().get(&path)?;
+    check_input(get_body(resp, &path)?)
+}
+
+/// Get the input data asynchronously, see [`get_input`]
+#[cfg(feature = "async")]
+pub async fn get_input_async(aoc: &Aoc) -> Result<String, Error> {
+    let path = format!("{}/input", get_path(aoc)?);
+    let resp = aoc.client().get_async(&path).await?;
+    check_input(get_body(resp, &path)?)
+}
+
+fn check_input(input: String) -> Result<String, Error> {
+    // this is most likely a login page
+    if is_html(&input) {
+        return Err(if input.contains("[Log In]") {
+            Error::Unauthenticated
+        } else {
+            let message = "unexpected HTML response for puzzle input".to_string();
+            Error::Server { status: 200, message }
+        });
+    }
     Ok(input)
 }
 
 #[cfg(feature = "html_parsing")]
 pub fn submit(aoc: &Aoc, solution: &str) -> Result<String, Error> {
-    let url = format!("{}/answer", get_url(aoc)?);
-    let cookie = format!("session={}", aoc.cookie);
-    let user_agent = user_agent();
+    let path = format!("{}/answer", get_path(aoc)?);
+    let resp = aoc.client().post_form(&path, &[
+        ("level", level_param(aoc.level)),
+        ("answer", solution),
+    ])?;
+    get_body(resp, &path)
+}
 
-    let level = match aoc.level {
+/// Submit a solution asynchronously, see [`submit`]
+#[cfg(all(feature = "html_parsing", feature = "async"))]
+pub async fn submit_async(aoc: &Aoc, solution: &str) -> Result<String, Error> {
+    let path = format!("{}/answer", get_path(aoc)?);
+    let resp = aoc.client().post_form_async(&path, &[
+        ("level", level_param(aoc.level)),
+        ("answer", solution),
+    ]).await?;
+    get_body(resp, &path)
+}
+
+#[cfg(feature = "html_parsing")]
+fn level_param(level: Level) -> &'static str {
+    match level {
         Level::First => "1",
         Level::Second => "2",
-    };
-
-    let resp = ureq::post(&url)
-        .set("COOKIE", &cookie)
-        .set("User-Agent", &user_agent)
-        .send_form(&[
-            ("level", level),
-            ("answer", solution),
-        ])?
-        .into_string()?;
-
-    let resp = get_html_section(&resp, "main").unwrap_or_default();
-    let resp = parse_html(&resp);
-    let resp = resp.trim().to_string();
-    Ok(resp)
+    }
 }
 
 #[cfg(feature = "html_parsing")]
@@ -96,14 +349,187 @@ fn get_html_section(contents: &str, section: &str) -> Option<String> {
     Some(html.to_string())
 }
 
-#[cfg(feature = "html_parsing")]
-pub fn verify(text: &str) -> bool {
-    text.contains("That's the right answer!")
-}
-
 #[cfg(feature = "html_parsing")]
 fn get_title(brief: &str) -> Option<String> {
     let regex = Regex::new("<h2>--- Day .*?: (.*?) ---</h2>").unwrap();
     let title = regex.captures(brief)?.get(1)?.as_str();
     Some(title.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MockTransport;
+    #[cfg(feature = "html_parsing")]
+    use tempfile::tempdir;
+
+    #[cfg(feature = "html_parsing")]
+    const BRIEF: &str = r#"<!DOCTYPE html>
+<html lang="en-us">
+<head><title>Day 1 - Advent of Code 2020</title></head>
+<body>
+<main>
+<article class="day-desc"><h2>--- Day 1: Report Repair ---</h2><p>Find the two entries that sum to <code>2020</code>.</p>
+<pre><code>1721
+979
+299
+</code></pre>
+<p>Multiplying them together produces <code><em>514579</em></code>.</p>
+</article>
+<p>To begin, <a href="1/input" target="_blank">get your puzzle input</a>.</p>
+<form method="post" action="1/answer"><input type="hidden" name="level" value="1"/><p>Answer: <input type="text" name="answer" autocomplete="off"/> <input type="submit" value="[Submit]"/></p></form>
+<p>You can also <span class="share">[Share...]</span> this puzzle.</p>
+</main>
+</body>
+</html>
+"#;
+
+    fn aoc(mock: &Arc<MockTransport>) -> Aoc {
+        Aoc::new()
+            .parse_cli(false)
+            .year(Some(2020))
+            .day(Some(1))
+            .cookie("secret")
+            .throttle(None)
+            .transport(mock.clone())
+    }
+
+    #[test]
+    fn test_get_input() {
+        let mock = Arc::new(MockTransport::new().route("/2020/day/1/input", 200, "1721\n979\n"));
+        let aoc = aoc(&mock);
+        assert_eq!(get_input(&aoc).unwrap(), "1721\n979\n");
+
+        let requests = mock.requests();
+        assert_eq!(requests[0].url, "https://adventofcode.com/2020/day/1/input");
+        assert_eq!(requests[0].header("cookie"), Some("session=secret"));
+        assert!(get_input(&aoc.day(Some(2))).is_err());
+    }
+
+    fn input_error(status: u16, body: &str) -> Error {
+        let mock = Arc::new(MockTransport::new().route("/2020/day/1/input", status, body));
+        get_input(&aoc(&mock)).unwrap_err()
+    }
+
+    #[test]
+    fn test_get_input_errors() {
+        let locked = "Please don't repeatedly request this endpoint before it unlocks! The calendar countdown is synchronized with the server time; the link will be enabled on the calendar the instant this puzzle becomes available.";
+        assert!(matches!(input_error(404, locked), Error::NotYetUnlocked));
+        let login = "Puzzle inputs differ by user.  Please log in to get your puzzle input.";
+        assert!(matches!(input_error(400, login), Error::Unauthenticated));
+        let page = "<!DOCTYPE html>\n<html><body><a href=\"/2020/auth/login\">[Log In]</a></body></html>";
+        assert!(matches!(input_error(200, page), Error::Unauthenticated));
+        assert!(matches!(input_error(500, "Internal Server Error"), Error::Server { status: 500, .. }));
+        assert!(matches!(input_error(200, "<html>oops</html>"), Error::Server { status: 200, .. }));
+    }
+
+    #[test]
+    fn test_base_url() {
+        let mock = Arc::new(MockTransport::new().route("/2020/day/1/input", 200, "1"));
+        let aoc = aoc(&mock).base_url("http://localhost:8080/");
+        assert_eq!(get_input(&aoc).unwrap(), "1");
+        assert_eq!(mock.requests()[0].url, "http://localhost:8080/2020/day/1/input");
+    }
+
+    #[test]
+    #[cfg(feature = "html_parsing")]
+    fn test_get_brief() {
+        let mock = Arc::new(MockTransport::new().route("/2020/day/1", 200, BRIEF));
+        let brief = get_brief(&aoc(&mock), None).unwrap().unwrap();
+        assert_eq!(brief.title, "Report Repair");
+        assert!(brief.brief.contains("Find the two entries that sum to `2020`."));
+        assert_eq!(brief.examples.len(), 1);
+        assert_eq!(brief.examples[0][0].input, "1721\n979\n299\n");
+        assert_eq!(brief.examples[0][0].answer.as_deref(), Some("514579"));
+        assert_eq!(brief.validators, None);
+
+        let mock = Arc::new(MockTransport::new().route("/2020/day/1", 200, "<html><body>maintenance</body></html>"));
+        assert!(matches!(get_brief(&aoc(&mock), None), Err(Error::Server { status: 200, .. })));
+    }
+
+    #[test]
+    #[cfg(feature = "html_parsing")]
+    fn test_get_brief_conditional() {
+        let page = Response::new(200, BRIEF).with_header("ETag", "\"abc\"");
+        let mock = Arc::new(MockTransport::new().response("/2020/day/1", page));
+        let tmp = tempdir().unwrap();
+        let cache = tmp.path().join("aoc2020_01.json");
+        let mut aoc = aoc(&mock).cache(Some(&cache)).init().unwrap();
+
+        let brief = aoc.get_brief(true).unwrap();
+        assert_eq!(aoc.get_brief(true).unwrap(), brief);
+
+        let requests = mock.requests();
+        assert_eq!(requests[0].header("If-None-Match"), None);
+        assert_eq!(requests[1].header("If-None-Match"), Some("\"abc\""));
+
+        // validators are kept in the cache
+        let mut aoc = Aoc::load_json_from(&cache).unwrap()
+            .throttle(None)
+            .transport(mock.clone());
+        aoc.get_brief(true).unwrap();
+        assert_eq!(mock.requests()[2].header("If-None-Match"), Some("\"abc\""));
+    }
+
+    #[test]
+    #[cfg(feature = "html_parsing")]
+    fn test_get_brief_not_modified() {
+        // not modified, with no brief cached to fall back on
+        let mock = Arc::new(MockTransport::new().route("/2020/day/1", 304, ""));
+        assert!(matches!(aoc(&mock).get_brief(true), Err(Error::Server { status: 304, .. })));
+    }
+
+    #[test]
+    #[cfg(feature = "html_parsing")]
+    fn test_submit() {
+        let answer = "<main><article><p>That's the right answer!  You are <span class=\"day-success\">one gold star</span> closer to saving your vacation.</p></article></main>";
+        let mock = Arc::new(MockTransport::new()
+            .route("/2020/day/1", 200, BRIEF)
+            .route("/2020/day/1/answer", 200, answer));
+        let tmp = tempdir().unwrap();
+        let cache = tmp.path().join("aoc2020_01.json");
+        let mut aoc = aoc(&mock).cache(Some(&cache)).init().unwrap();
+
+        assert!(aoc.submit("514579").unwrap().is_correct());
+        assert_eq!(aoc.level, Level::Second);
+        assert_eq!(aoc.stars, Some(1));
+        assert_eq!(aoc.title.as_deref(), Some("Report Repair"));
+
+        let requests = mock.requests();
+        assert_eq!(requests[0].method, Method::Post);
+        assert_eq!(requests[0].form, vec![
+            ("level".to_string(), "1".to_string()),
+            ("answer".to_string(), "514579".to_string()),
+        ]);
+
+        let cached = Aoc::load_json_from(&cache).unwrap();
+        assert_eq!(cached.solution.get(&Level::First).map(String::as_str), Some("514579"));
+        assert_eq!(cached.submissions.len(), 1);
+    }
+
+    #[tokio::test]
+    #[cfg(all(feature = "html_parsing", feature = "async"))]
+    async fn test_async() {
+        let answer = "<main><article><p>That's the right answer!</p></article></main>";
+        let mock = Arc::new(MockTransport::new()
+            .route("/2020/day/1", 200, BRIEF)
+            .route("/2020/day/1/input", 200, "1721\n979\n")
+            .route("/2020/day/1/answer", 200, answer));
+        let tmp = tempdir().unwrap();
+        let cache = tmp.path().join("aoc2020_01.json");
+        let mut aoc = aoc(&mock).async_transport(mock.clone()).cache(Some(&cache)).init().unwrap();
+
+        assert_eq!(get_input_async(&aoc).await.unwrap(), "1721\n979\n");
+        let brief = aoc.get_brief_async(false).await.unwrap();
+        assert!(aoc.submit_async("514579").await.unwrap().is_correct());
+        assert_eq!(aoc.level, Level::Second);
+        assert_eq!(mock.requests().len(), 4);
+
+        // the cache is shared with the blocking API
+        let mut aoc = aoc.cache(Some(&cache)).init().unwrap();
+        aoc.level = Level::First;
+        assert_eq!(aoc.get_brief(false).unwrap(), brief);
+        assert_eq!(aoc.submissions.len(), 1);
+        assert_eq!(mock.requests().len(), 4);
+    }
+}
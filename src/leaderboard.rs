@@ -0,0 +1,420 @@
+//! Private leaderboards
+//!
+//! Leaderboards are fetched as JSON from
+//! `/{year}/leaderboard/private/view/{id}.json`, independently of any [`Aoc`]
+//! problem. The site asks that this is done no more than once every 15
+//! minutes, so responses are cached on disk, see [`get`]. Global leaderboards
+//! are parsed from HTML, with the `html_parsing` feature, see [`global`].
+//!
+//! [`Aoc`]: crate::Aoc
+use crate::http::{get_body, is_html};
+use crate::release::release_time;
+use crate::{Client, Error, Level, Response, ensure_parent_dir, find_root, profile};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(feature = "html_parsing")]
+pub mod global;
+
+/// Minimum interval between requests for the same leaderboard
+pub const MIN_REFRESH: Duration = Duration::from_secs(15 * 60);
+
+/// A private leaderboard for one year's event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Leaderboard {
+    #[serde(rename = "event", with = "flexible_int")]
+    pub year: i32,
+    #[serde(with = "flexible_int")]
+    pub owner_id: u64,
+    /// Members of the leaderboard, by ID
+    pub members: BTreeMap<u64, Member>,
+}
+
+/// A member of a private leaderboard
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Member {
+    #[serde(with = "flexible_int")]
+    pub id: u64,
+    /// Name of the member, if they aren't anonymous
+    pub name: Option<String>,
+    #[serde(with = "flexible_int")]
+    pub stars: u32,
+    #[serde(with = "flexible_int")]
+    pub local_score: u32,
+    #[serde(default, with = "flexible_int")]
+    pub global_score: u32,
+    /// Time the last star was gained, if any have been
+    #[serde(default, with = "optional_timestamp")]
+    pub last_star_ts: Option<DateTime<Utc>>,
+    /// Stars gained for each day
+    #[serde(default)]
+    pub completion_day_level: BTreeMap<u32, DayCompletion>,
+}
+
+/// Stars gained by a member for a day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayCompletion {
+    #[serde(rename = "1")]
+    pub first: Star,
+    #[serde(rename = "2", default, skip_serializing_if = "Option::is_none")]
+    pub second: Option<Star>,
+}
+
+/// A star gained by a member
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Star {
+    /// Time the star was gained
+    #[serde(with = "timestamp")]
+    pub get_star_ts: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub star_index: Option<u64>,
+}
+
+/// Times taken by a member to solve a day's puzzle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Split {
+    /// ID of the member
+    pub member: u64,
+    pub day: u32,
+    /// Time from the puzzle unlocking to the first star
+    pub first: Duration,
+    /// Time from the puzzle unlocking to the second star
+    pub second: Option<Duration>,
+}
+
+impl Split {
+    /// Get the time between the first and second stars
+    pub fn delta(&self) -> Option<Duration> {
+        Some(self.second?.saturating_sub(self.first))
+    }
+}
+
+impl Leaderboard {
+    /// Parse a leaderboard from the site's JSON representation
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Get the split times for every member and day with at least one star,
+    /// ordered by day and member ID
+    pub fn splits(&self) -> Vec<Split> {
+        let mut splits: Vec<_> = self.members.values()
+            .flat_map(|m| m.splits(self.year))
+            .collect();
+        splits.sort_by_key(|s| (s.day, s.member));
+        splits
+    }
+}
+
+impl Member {
+    /// Get the member's name, or the name shown by the site for anonymous
+    /// members
+    pub fn display_name(&self) -> String {
+        match &self.name {
+            Some(n) => n.clone(),
+            None => format!("(anonymous user #{})", self.id),
+        }
+    }
+
+    /// Get the member's split times for each day with at least one star, in
+    /// the given year
+    pub fn splits(&self, year: i32) -> Vec<Split> {
+        self.completion_day_level.keys()
+            .filter_map(|d| self.split(year, *d))
+            .collect()
+    }
+
+    /// Get the member's split times for a day in the given year, if they
+    /// have at least one star for it
+    pub fn split(&self, year: i32, day: u32) -> Option<Split> {
+        let unlock = release_time(year, day)?;
+        let since_unlock = |ts: DateTime<Utc>| (ts - unlock).to_std().unwrap_or_default();
+        Some(Split {
+            member: self.id,
+            day,
+            first: since_unlock(self.star_ts(day, Level::First)?),
+            second: self.star_ts(day, Level::Second).map(since_unlock),
+        })
+    }
+
+    /// Get the time a star was gained for a day and level
+    pub fn star_ts(&self, day: u32, level: Level) -> Option<DateTime<Utc>> {
+        let completion = self.completion_day_level.get(&day)?;
+        match level {
+            Level::First => Some(completion.first.get_star_ts),
+            Level::Second => completion.second.as_ref().map(|s| s.get_star_ts),
+        }
+    }
+}
+
+/// A cached leaderboard, with the time it was fetched
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    fetched: DateTime<Utc>,
+    leaderboard: Leaderboard,
+}
+
+impl CacheEntry {
+    /// Get the time until the leaderboard may be fetched again
+    fn refresh_in(&self) -> Duration {
+        let age = (Utc::now() - self.fetched).to_std().unwrap_or_default();
+        MIN_REFRESH.saturating_sub(age)
+    }
+}
+
+fn path(year: i32, id: u64) -> String {
+    format!("/{}/leaderboard/private/view/{}.json", year, id)
+}
+
+fn parse_response(resp: Response, path: &str, id: u64) -> Result<Leaderboard, Error> {
+    let body = get_body(resp, path)?;
+    // the site redirects to an HTML page for leaderboards which can't be
+    // viewed
+    if is_html(&body) {
+        return Err(if body.contains("[Log In]") {
+            Error::Unauthenticated
+        } else {
+            let message = format!("private leaderboard {} doesn't exist, or can't be viewed", id);
+            Error::Server { status: 200, message }
+        });
+    }
+    Leaderboard::from_json(&body)
+}
+
+/// Fetch a private leaderboard, bypassing the cache
+pub fn fetch(client: &Client, year: i32, id: u64) -> Result<Leaderboard, Error> {
+    let path = path(year, id);
+    let resp = client.get(&path)?;
+    parse_response(resp, &path, id)
+}
+
+/// Fetch a private leaderboard asynchronously, bypassing the cache
+#[cfg(feature = "async")]
+pub async fn fetch_async(client: &Client, year: i32, id: u64) -> Result<Leaderboard, Error> {
+    let path = path(year, id);
+    let resp = client.get_async(&path).await?;
+    parse_response(resp, &path, id)
+}
+
+/// Get a private leaderboard, from the cache in a directory if it was
+/// fetched less than [`MIN_REFRESH`] ago
+///
+/// Otherwise the leaderboard is fetched, and the cache updated.
+pub fn get(client: &Client, year: i32, id: u64, cache_dir: &Path) -> Result<Leaderboard, Error> {
+    if let Some(leaderboard) = load_fresh(cache_dir, year, id) {
+        return Ok(leaderboard);
+    }
+    let leaderboard = fetch(client, year, id)?;
+    store(cache_dir, &leaderboard, id)?;
+    Ok(leaderboard)
+}
+
+/// Get a private leaderboard asynchronously, see [`get`]
+#[cfg(feature = "async")]
+pub async fn get_async(client: &Client, year: i32, id: u64, cache_dir: &Path) -> Result<Leaderboard, Error> {
+    if let Some(leaderboard) = load_fresh(cache_dir, year, id) {
+        return Ok(leaderboard);
+    }
+    let leaderboard = fetch_async(client, year, id).await?;
+    store(cache_dir, &leaderboard, id)?;
+    Ok(leaderboard)
+}
+
+/// Get the default directory leaderboards are cached in, `leaderboard` in
+/// the profile's directory in the aocf root configuration, e.g.
+/// `.aocf/leaderboard` for the default profile
+pub fn default_cache_dir(profile: Option<&str>) -> Result<PathBuf, Error> {
+    Ok(find_root()?.join(profile::dir(profile)?).join("leaderboard"))
+}
+
+/// Get the time until a cached leaderboard may be fetched again, if it's
+/// cached at all
+pub fn next_refresh(cache_dir: &Path, year: i32, id: u64) -> Option<Duration> {
+    load(cache_dir, year, id).map(|e| e.refresh_in())
+}
+
+fn cache_file(cache_dir: &Path, year: i32, id: u64) -> PathBuf {
+    cache_dir.join(format!("{}_{}.json", year, id))
+}
+
+fn load(cache_dir: &Path, year: i32, id: u64) -> Option<CacheEntry> {
+    let json = read_to_string(cache_file(cache_dir, year, id)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn load_fresh(cache_dir: &Path, year: i32, id: u64) -> Option<Leaderboard> {
+    load(cache_dir, year, id)
+        .filter(|e| e.refresh_in() > Duration::ZERO)
+        .map(|e| e.leaderboard)
+}
+
+fn store(cache_dir: &Path, leaderboard: &Leaderboard, id: u64) -> Result<(), Error> {
+    let file = cache_file(cache_dir, leaderboard.year, id);
+    ensure_parent_dir(&file)?;
+    let entry = CacheEntry { fetched: Utc::now(), leaderboard: leaderboard.clone() };
+    write(file, serde_json::to_string_pretty(&entry)?)?;
+    Ok(())
+}
+
+/// Integers which may be given either as numbers or as strings, as they are
+/// for some older events
+mod flexible_int {
+    use super::*;
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flexible<T> {
+        Number(T),
+        String(String),
+    }
+
+    pub fn serialize<T: Serialize, S: Serializer>(value: &T, s: S) -> Result<S::Ok, S::Error> {
+        value.serialize(s)
+    }
+
+    pub fn deserialize<'de, T, D>(d: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de> + FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        match Flexible::<T>::deserialize(d)? {
+            Flexible::Number(n) => Ok(n),
+            Flexible::String(s) => s.trim().parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Unix timestamps, in seconds
+mod timestamp {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(ts: &DateTime<Utc>, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_i64(ts.timestamp())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<DateTime<Utc>, D::Error> {
+        let secs: i64 = flexible_int::deserialize(d)?;
+        Utc.timestamp_opt(secs, 0)
+            .single()
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid timestamp {}", secs)))
+    }
+}
+
+/// Unix timestamps, in seconds, where zero means none
+mod optional_timestamp {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(ts: &Option<DateTime<Utc>>, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_i64(ts.map_or(0, |t| t.timestamp()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+        let secs: i64 = flexible_int::deserialize(d)?;
+        Ok(Utc.timestamp_opt(secs, 0).single().filter(|_| secs != 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockTransport;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    const BOARD: &str = r#"{
+"owner_id": 12345,
+"event": "2020",
+"members": {
+  "12345": {
+    "id": 12345, "name": "owner", "stars": 3, "local_score": 10, "global_score": 0,
+    "last_star_ts": 1606804270,
+    "completion_day_level": {
+      "1": {"1": {"get_star_ts": 1606799999, "star_index": 1}, "2": {"get_star_ts": 1606804270, "star_index": 2}},
+      "2": {"1": {"get_star_ts": 1606890000, "star_index": 3}}
+    }
+  },
+  "678": {
+    "id": 678, "name": null, "stars": 0, "local_score": 0, "global_score": 0,
+    "last_star_ts": 0, "completion_day_level": {}
+  }
+}
+}"#;
+
+    // older events give numbers as strings
+    const OLD_BOARD: &str = r#"{"owner_id":"12345","event":"2018","members":{"12345":{"id":"12345","name":"owner","stars":1,"local_score":"2","global_score":0,"last_star_ts":"1543640100","completion_day_level":{"1":{"1":{"get_star_ts":"1543640100"}}}}}}"#;
+
+    fn client(mock: &Arc<MockTransport>) -> Client {
+        Client::new("secret").throttle(None).transport(mock.clone())
+    }
+
+    #[test]
+    fn test_parse_leaderboard() {
+        let board = Leaderboard::from_json(BOARD).unwrap();
+        assert_eq!(board.year, 2020);
+        assert_eq!(board.members.len(), 2);
+
+        let owner = &board.members[&12345];
+        assert_eq!(owner.stars, 3);
+        assert_eq!(owner.local_score, 10);
+        assert_eq!(owner.star_ts(1, Level::Second), Utc.timestamp_opt(1606804270, 0).single());
+        assert_eq!(owner.star_ts(2, Level::Second), None);
+        assert_eq!(owner.star_ts(3, Level::First), None);
+
+        let anon = &board.members[&678];
+        assert_eq!(anon.display_name(), "(anonymous user #678)");
+        assert_eq!(anon.last_star_ts, None);
+
+        let old = Leaderboard::from_json(OLD_BOARD).unwrap();
+        assert_eq!(old.members[&12345].local_score, 2);
+        assert_eq!(old.members[&12345].star_ts(1, Level::First).unwrap().timestamp(), 1543640100);
+    }
+
+    #[test]
+    fn test_splits() {
+        let board = Leaderboard::from_json(BOARD).unwrap();
+        let splits = board.splits();
+        assert_eq!(splits.len(), 2);
+
+        // day 1 of 2020 unlocked at 1606798800
+        assert_eq!(splits[0], Split {
+            member: 12345,
+            day: 1,
+            first: Duration::from_secs(1199),
+            second: Some(Duration::from_secs(5470)),
+        });
+        assert_eq!(splits[0].delta(), Some(Duration::from_secs(4271)));
+        assert_eq!(splits[1].day, 2);
+        assert_eq!(splits[1].delta(), None);
+        assert_eq!(board.members[&678].split(2020, 1), None);
+    }
+
+    #[test]
+    fn test_get_cached() {
+        let mock = Arc::new(MockTransport::new()
+            .route("/2020/leaderboard/private/view/12345.json", 200, BOARD));
+        let tmp = tempdir().unwrap();
+
+        let board = get(&client(&mock), 2020, 12345, tmp.path()).unwrap();
+        assert_eq!(get(&client(&mock), 2020, 12345, tmp.path()).unwrap(), board);
+        assert_eq!(mock.requests().len(), 1);
+        assert!(next_refresh(tmp.path(), 2020, 12345).unwrap() > Duration::from_secs(14 * 60));
+        assert_eq!(next_refresh(tmp.path(), 2020, 1), None);
+    }
+
+    #[test]
+    fn test_fetch_errors() {
+        let page = "<!DOCTYPE html>\n<html><body>leaderboard</body></html>";
+        let mock = Arc::new(MockTransport::new()
+            .route("/2020/leaderboard/private/view/1.json", 200, page));
+        let err = fetch(&client(&mock), 2020, 1).unwrap_err();
+        assert!(matches!(err, Error::Server { status: 200, .. }));
+        assert!(matches!(fetch(&client(&mock), 2020, 2).unwrap_err(), Error::Server { status: 404, .. }));
+    }
+}
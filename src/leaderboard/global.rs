@@ -0,0 +1,163 @@
+//! Global leaderboards
+//!
+//! The top 100 for each day are only given as HTML, at
+//! `/{year}/leaderboard/day/{day}`, so are parsed from the page.
+use crate::examples::decode;
+use crate::http::get_body;
+use crate::{Client, Error, Level};
+use regex::Regex;
+use std::time::Duration;
+
+/// Number of places on a global leaderboard
+pub const PLACES: usize = 100;
+
+/// The global leaderboard for a day
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GlobalLeaderboard {
+    pub year: i32,
+    pub day: u32,
+    /// The first users to get the first star
+    pub first: Vec<GlobalEntry>,
+    /// The first users to get both stars
+    pub second: Vec<GlobalEntry>,
+}
+
+/// A place on a global leaderboard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalEntry {
+    /// Rank, shared by users tied for a place
+    pub rank: u32,
+    /// Time from the puzzle unlocking to getting the star
+    pub time: Duration,
+    pub user: String,
+    /// Whether the user is an AoC++ supporter
+    pub supporter: bool,
+}
+
+impl GlobalLeaderboard {
+    /// Parse the global leaderboard for a day from its page
+    pub fn from_html(html: &str, year: i32, day: u32) -> Self {
+        let both = Regex::new(r#"<span class="leaderboard-daydesc-both">"#).unwrap();
+        let first = Regex::new(r#"<span class="leaderboard-daydesc-first">"#).unwrap();
+        let entry = Regex::new(r#"(?s)<div class="leaderboard-entry">(.*?)</div>"#).unwrap();
+
+        // entries belong to the section introduced before them
+        let mut sections: Vec<_> = both.find_iter(html).map(|m| (m.start(), Level::Second))
+            .chain(first.find_iter(html).map(|m| (m.start(), Level::First)))
+            .collect();
+        sections.sort_by_key(|(start, _)| *start);
+
+        let mut board = Self { year, day, ..Default::default() };
+        for m in entry.captures_iter(html) {
+            let start = m.get(0).unwrap().start();
+            let level = match sections.iter().rev().find(|(s, _)| *s < start) {
+                Some((_, level)) => *level,
+                None => continue,
+            };
+            let entries = match level {
+                Level::First => &mut board.first,
+                Level::Second => &mut board.second,
+            };
+            let previous = entries.last().map(|e: &GlobalEntry| e.rank);
+            if let Some(e) = parse_entry(&m[1], day, previous) {
+                entries.push(e);
+            }
+        }
+
+        board
+    }
+
+    /// Get the entries for a level
+    pub fn entries(&self, level: Level) -> &[GlobalEntry] {
+        match level {
+            Level::First => &self.first,
+            Level::Second => &self.second,
+        }
+    }
+
+    /// Get the time the leaderboard for a level was capped at, the time of
+    /// the last place, if all places have been taken
+    pub fn capped(&self, level: Level) -> Option<Duration> {
+        let entries = self.entries(level);
+        match entries.len() {
+            n if n >= PLACES => entries.last().map(|e| e.time),
+            _ => None,
+        }
+    }
+}
+
+/// Parse an entry, users tied with the previous entry are given without a
+/// position, and share its rank
+fn parse_entry(html: &str, day: u32, previous: Option<u32>) -> Option<GlobalEntry> {
+    let position = Regex::new(r#"(?s)<span class="leaderboard-position">(.*?)</span>"#).unwrap();
+    let time = Regex::new(r#"(?s)<span class="leaderboard-time">(.*?)</span>"#).unwrap();
+    let badge = Regex::new(r#"(?s)<a [^>]*class="supporter-badge"[^>]*>.*?</a>"#).unwrap();
+
+    let rank = position.captures(html)
+        .and_then(|c| decode(&c[1]).trim().trim_end_matches(')').parse().ok())
+        .or(previous)?;
+    let supporter = badge.is_match(html);
+
+    // the user is what's left after removing everything else
+    let user = badge.replace_all(html, "");
+    let user = position.replace_all(&user, "");
+    let user = decode(&time.replace_all(&user, "")).trim().to_string();
+
+    let time = parse_time(&decode(&time.captures(html)?[1]), day)?;
+
+    Some(GlobalEntry { rank, time, user, supporter })
+}
+
+/// Parse a time such as `Dec 01  00:00:53` to the time since the day's
+/// puzzle unlocked, times are given in the site's time zone
+fn parse_time(text: &str, day: u32) -> Option<Duration> {
+    let regex = Regex::new(r"Dec\s+(\d+)\s+(\d+):(\d+):(\d+)").unwrap();
+    let c = regex.captures(text)?;
+    let field = |i: usize| c[i].parse::<u64>().ok();
+    let days = field(1)?.checked_sub(day.into())?;
+    let secs = days * 86400 + field(2)? * 3600 + field(3)? * 60 + field(4)?;
+    Some(Duration::from_secs(secs))
+}
+
+/// Fetch the global leaderboard for a day
+pub fn fetch(client: &Client, year: i32, day: u32) -> Result<GlobalLeaderboard, Error> {
+    let path = format!("/{}/leaderboard/day/{}", year, day);
+    let resp = client.get(&path)?;
+    Ok(GlobalLeaderboard::from_html(&get_body(resp, &path)?, year, day))
+}
+
+/// Fetch the global leaderboard for a day asynchronously
+#[cfg(feature = "async")]
+pub async fn fetch_async(client: &Client, year: i32, day: u32) -> Result<GlobalLeaderboard, Error> {
+    let path = format!("/{}/leaderboard/day/{}", year, day);
+    let resp = client.get_async(&path).await?;
+    Ok(GlobalLeaderboard::from_html(&get_body(resp, &path)?, year, day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<main>
+<p>First hundred users to get <span class="leaderboard-daydesc-both">both stars</span> on Day 1:</p>
+<div class="leaderboard-entry"><span class="leaderboard-position">  1)</span> <span class="leaderboard-time">Dec 01  00:01:12</span> <a href="https://github.com/someone" target="_blank"><span class="leaderboard-userphoto"><img src="https://avatars.githubusercontent.com/u/1?v=4" height="20"/></span>some&amp;one</a> <a href="/2020/support" class="supporter-badge" title="Advent of Code Supporter">(AoC++)</a></div>
+<div class="leaderboard-entry"><span class="leaderboard-position">  2)</span> <span class="leaderboard-time">Dec 01  00:01:30</span> <span class="leaderboard-userphoto"></span><span class="leaderboard-anon">(anonymous user #123)</span></div>
+<div class="leaderboard-entry"><span class="leaderboard-position">    </span> <span class="leaderboard-time">Dec 01  00:01:30</span> <span class="leaderboard-userphoto"></span>tied</div>
+<p>First hundred users to get the <span class="leaderboard-daydesc-first">first star</span> on Day 1:</p>
+<div class="leaderboard-entry"><span class="leaderboard-position">  1)</span> <span class="leaderboard-time">Dec 02  00:00:53</span> <span class="leaderboard-userphoto"></span>slow</div>
+</main>"#;
+
+    #[test]
+    fn test_parse_global() {
+        let board = GlobalLeaderboard::from_html(PAGE, 2020, 1);
+        assert_eq!(board.second, vec![
+            GlobalEntry { rank: 1, time: Duration::from_secs(72), user: "some&one".into(), supporter: true },
+            GlobalEntry { rank: 2, time: Duration::from_secs(90), user: "(anonymous user #123)".into(), supporter: false },
+            GlobalEntry { rank: 2, time: Duration::from_secs(90), user: "tied".into(), supporter: false },
+        ]);
+        assert_eq!(board.first, vec![
+            GlobalEntry { rank: 1, time: Duration::from_secs(86453), user: "slow".into(), supporter: false },
+        ]);
+        assert_eq!(board.capped(Level::Second), None);
+    }
+}
@@ -8,31 +8,63 @@ use std::fs::{File, read_to_string, create_dir_all};
 use std::io::{self, Write, BufRead};
 use std::path::{Path, PathBuf};
 use std::env::current_dir;
+use std::sync::Arc;
+use std::time::Duration;
 use serde::{Serialize, Serializer};
-use failure::{Error, bail};
+use chrono::{DateTime, Utc};
 
+mod error;
+#[cfg(any(feature = "sqlite", feature = "encrypted_cookie"))]
+mod hex;
 mod http;
+mod transport;
+pub mod throttle;
 #[cfg(feature = "sqlite")]
 pub mod cookie;
 mod cli;
+pub mod release;
+pub mod leaderboard;
+pub mod profile;
+pub mod secret;
+mod submission;
+mod examples;
+#[cfg(feature = "html_parsing")]
+pub mod account;
+#[cfg(feature = "html_parsing")]
+pub mod calendar;
+#[cfg(feature = "html_parsing")]
+pub mod events;
+
+pub use error::Error;
+pub use http::{Client, Validators};
+pub use leaderboard::Leaderboard;
+pub use release::Release;
+pub use secret::{SecretStore, Storage};
+pub use throttle::Throttle;
+pub use transport::{Method, MockTransport, Request, Response, Transport, UreqTransport};
+#[cfg(feature = "async")]
+pub use transport::{AsyncTransport, ReqwestTransport, ResponseFuture};
+pub use submission::{Hint, Submission, SubmitOutcome};
+pub use examples::Example;
+#[cfg(feature = "html_parsing")]
+pub use account::Account;
+#[cfg(feature = "html_parsing")]
+pub use calendar::Calendar;
+#[cfg(feature = "html_parsing")]
+pub use events::Events;
 
 use cli::AocOpts;
 use clap::Parser;
 use atty::{is, Stream};
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Default, Hash, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Level {
+    #[default]
     First,
     Second,
 }
 
-impl Default for Level {
-    fn default() -> Self {
-        Self::First
-    }
-}
-
 impl fmt::Display for Level {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let s = match self {
@@ -52,10 +84,20 @@ pub struct Aoc {
     pub title: Option<String>,
     pub stars: Option<u8>,
     pub solution: HashMap<Level, String>,
+    /// History of all answers submitted
+    #[serde(default)]
+    pub submissions: Vec<Submission>,
+    /// Time before which no further answers may be submitted
+    pub cooldown: Option<DateTime<Utc>>,
 
     input: Option<String>,
     #[serde(serialize_with = "ordered_map")]
     brief: HashMap<Level, String>,
+    /// Examples extracted from the brief
+    #[serde(default, serialize_with = "ordered_map")]
+    examples: HashMap<Level, Vec<Example>>,
+    /// Validators from the last response for the brief
+    brief_validators: Option<Validators>,
     #[serde(serialize_with = "ordered_map")]
 
     #[serde(skip)]
@@ -64,6 +106,31 @@ pub struct Aoc {
     cache_path: Option<PathBuf>,
     #[serde(skip)]
     cookie_path: Option<PathBuf>,
+    /// Account profile, if not the default
+    #[serde(skip)]
+    profile: Option<String>,
+    /// Store to get the cookie from, if not found from the profile
+    #[serde(skip)]
+    secret_store: Option<Arc<dyn SecretStore>>,
+    /// Passphrase for an encrypted cookie file, if not from the environment
+    #[serde(skip)]
+    passphrase: Option<String>,
+    /// Base URL to make requests to, if not the default
+    #[serde(skip)]
+    base_url: Option<String>,
+    /// Transport used to make requests, if not the default
+    #[serde(skip)]
+    transport: Option<Arc<dyn Transport>>,
+    /// Transport used to make asynchronous requests, if not the default
+    #[cfg(feature = "async")]
+    #[serde(skip)]
+    async_transport: Option<Arc<dyn AsyncTransport>>,
+    /// Throttle for requests, if enabled
+    #[serde(skip)]
+    throttle: Option<Throttle>,
+    /// Called with the delay whenever a request is throttled
+    #[serde(skip)]
+    on_throttle: Option<fn(Duration)>,
     /// Whether to parse CLI arguments locally
     #[serde(skip)]
     parse_cli: bool,
@@ -77,7 +144,7 @@ pub struct Aoc {
 
 impl Aoc {
     pub fn new() -> Self {
-        Aoc { parse_cli: true, ..Default::default() }
+        Aoc { parse_cli: true, throttle: Some(Throttle::default()), ..Default::default() }
     }
 
     /// Set the year
@@ -98,17 +165,85 @@ impl Aoc {
         self
     }
 
+    /// Set the account profile to use the cookie and cache of
+    ///
+    /// Profiles are kept in `.aocf/profiles/<name>`, see [`profile`].
+    pub fn profile(mut self, profile: Option<&str>) -> Self {
+        self.profile = profile.map(String::from);
+        self
+    }
+
+    /// Set the store to get the cookie from
+    ///
+    /// By default, the cookie is found in the profile's directory, however
+    /// it's stored, see [`secret`].
+    pub fn secret_store(mut self, store: impl SecretStore + 'static) -> Self {
+        self.secret_store = Some(Arc::new(store));
+        self
+    }
+
+    /// Set the passphrase for the profile's cookie, if it's encrypted
+    ///
+    /// Defaults to the value of the `AOCF_PASSPHRASE` environment variable.
+    pub fn passphrase(mut self, passphrase: Option<&str>) -> Self {
+        self.passphrase = passphrase.map(String::from);
+        self
+    }
+
     /// Set cookie file
     pub fn cookie_file(mut self, path: impl AsRef<Path>) -> Self {
         self.cookie_path = Some(path.as_ref().to_path_buf());
         self
     }
 
+    /// Set the base URL requests are made to
+    ///
+    /// Defaults to `https://adventofcode.com`.
+    pub fn base_url(mut self, url: &str) -> Self {
+        self.base_url = Some(url.to_string());
+        self
+    }
+
+    /// Set the transport used to make HTTP requests
+    ///
+    /// For example, a [`MockTransport`] may be used to serve recorded
+    /// responses rather than making real requests.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Set the transport used to make HTTP requests from the async API
+    ///
+    /// A [`MockTransport`] may be used for this too.
+    #[cfg(feature = "async")]
+    pub fn async_transport(mut self, transport: impl AsyncTransport + 'static) -> Self {
+        self.async_transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Set the minimum interval between requests, or disable throttling
+    ///
+    /// Requests are throttled to one every 3 seconds by default. The time
+    /// of the last request is shared with other processes using the same
+    /// aocf root configuration.
+    pub fn throttle(mut self, interval: Option<Duration>) -> Self {
+        self.throttle = interval.map(Throttle::new);
+        self
+    }
+
+    /// Set a function to call with the delay whenever a request is
+    /// throttled, e.g. to tell the user why it's waiting
+    pub fn on_throttle(mut self, callback: fn(Duration)) -> Self {
+        self.on_throttle = Some(callback);
+        self
+    }
+
     /// Set the cache path
 //    pub fn cache<P>(&mut self, path: P) -> &mut Self
 //        where P: AsRef<Path> + std::clone::Clone,
 //    {
-    pub fn cache<P>(mut self, path: Option<&Path>) -> Self {
+    pub fn cache(mut self, path: Option<&Path>) -> Self {
         self.cache_path = path.as_ref().map(PathBuf::from);
         self
     }
@@ -127,10 +262,12 @@ impl Aoc {
     pub fn init(mut self) -> Result<Self, Error> {
         // Attempt to load cookie data
         if self.cookie.is_empty() {
-            if let Some(p) = &self.cookie_path {
-                self.cookie = read_to_string(p)?.trim().to_string()
-            } else if let Ok(p) = self.get_default_cookie_path() {
-                self.cookie = read_to_string(p)?.trim().to_string()
+            self.cookie = if let Some(s) = &self.secret_store {
+                s.get()?
+            } else if let Some(p) = &self.cookie_path {
+                secret::PlaintextFile::new(p).get()?
+            } else {
+                secret::resolve(self.get_profile_dir()?, self.profile.as_deref(), self.passphrase.as_deref())?.get()?
             };
         }
 
@@ -146,6 +283,17 @@ impl Aoc {
         if let Ok(mut aoc) = self.load() {
             // re-instate fields which will need to be overriden after successful load
             aoc.cookie = self.cookie;
+            aoc.cache_path = self.cache_path;
+            aoc.profile = self.profile;
+            aoc.secret_store = self.secret_store;
+            aoc.base_url = self.base_url;
+            aoc.transport = self.transport;
+            #[cfg(feature = "async")]
+            {
+                aoc.async_transport = self.async_transport;
+            }
+            aoc.throttle = self.throttle;
+            aoc.on_throttle = self.on_throttle;
             aoc.input_file = self.input_file;
             aoc.stream = self.stream;
             Ok(aoc)
@@ -157,20 +305,120 @@ impl Aoc {
     /// Get the problem brief as HTML and sanitise it to markdown
     #[cfg(feature = "html_parsing")]
     pub fn get_brief(&mut self, force: bool) -> Result<String, Error> {
-        if self.brief.get(&self.level).is_none() || force {
-            let brief = http::get_brief(self)?;
-            self.title = Some(brief.0);
-            self.brief.insert(self.level, brief.1);
+        if self.should_fetch_brief(force)? {
+            let brief = http::get_brief(self, self.brief_validators())?;
+            self.store_brief(brief)?;
+        }
+        self.cached_brief()
+    }
+
+    /// Get the problem brief asynchronously, see [`Aoc::get_brief`]
+    ///
+    /// The brief is cached in the same way as by the blocking API.
+    #[cfg(all(feature = "html_parsing", feature = "async"))]
+    pub async fn get_brief_async(&mut self, force: bool) -> Result<String, Error> {
+        if self.should_fetch_brief(force)? {
+            let brief = http::get_brief_async(self, self.brief_validators()).await?;
+            self.store_brief(brief)?;
+        }
+        self.cached_brief()
+    }
+
+    #[cfg(feature = "html_parsing")]
+    fn should_fetch_brief(&self, force: bool) -> Result<bool, Error> {
+        if self.brief.contains_key(&self.level) && !force {
+            return Ok(false);
+        }
+        if let Release::Pending(_) = self.get_time_until_release()? {
+            return Err(Error::NotYetUnlocked);
+        }
+        Ok(true)
+    }
+
+    /// Get validators for a conditional request for the brief
+    ///
+    /// A conditional request can only be made if there's a cached brief for
+    /// the current level to fall back on.
+    #[cfg(feature = "html_parsing")]
+    fn brief_validators(&self) -> Option<&Validators> {
+        self.brief.get(&self.level).and(self.brief_validators.as_ref())
+    }
+
+    /// Cache a fetched brief, `None` meaning the cached brief is up to date
+    #[cfg(feature = "html_parsing")]
+    fn store_brief(&mut self, brief: Option<http::Brief>) -> Result<(), Error> {
+        if let Some(brief) = brief {
+            self.title = Some(brief.title);
+            self.brief.insert(self.level, brief.brief);
+            for (level, examples) in [Level::First, Level::Second].iter().zip(brief.examples) {
+                self.examples.insert(*level, examples);
+            }
+            self.brief_validators = brief.validators;
             self.write()?;
-        };
-        Ok(self.brief.get(&self.level).unwrap().to_string())
+        }
+        Ok(())
+    }
+
+    /// Get the cached brief for the current level
+    ///
+    /// The server may claim the brief isn't modified even when none is
+    /// cached, e.g. if a proxy answers the request.
+    #[cfg(feature = "html_parsing")]
+    fn cached_brief(&self) -> Result<String, Error> {
+        self.brief.get(&self.level).cloned().ok_or_else(|| {
+            let message = "brief not modified, but none is cached".to_string();
+            Error::Server { status: 304, message }
+        })
+    }
+
+    /// Get the examples, and their expected answers, given in the brief for
+    /// a level
+    ///
+    /// The brief is fetched if examples for the level aren't yet cached. No
+    /// examples are returned for a level which isn't yet unlocked.
+    #[cfg(feature = "html_parsing")]
+    pub fn examples(&mut self, level: Level) -> Result<Vec<Example>, Error> {
+        if !self.examples.contains_key(&level) && level <= self.level {
+            self.get_brief(true)?;
+        }
+        Ok(self.examples.get(&level).cloned().unwrap_or_default())
     }
 
     /// Get the input data
+    ///
+    /// Fails with an [`Error`] if the puzzle isn't yet unlocked, the
+    /// session cookie isn't valid, or the server doesn't give the input.
     pub fn get_input(&mut self, force: bool) -> Result<String, Error> {
+        if let Some(input) = self.local_input()? {
+            return Ok(input);
+        }
+        if self.should_fetch_input(force)? {
+            let input = http::get_input(self)?;
+            self.store_input(input)?;
+        }
+        Ok(self.input.clone().unwrap())
+    }
+
+    /// Get the input data asynchronously, see [`Aoc::get_input`]
+    ///
+    /// The input is cached in the same way as by the blocking API.
+    #[cfg(feature = "async")]
+    pub async fn get_input_async(&mut self, force: bool) -> Result<String, Error> {
+        if let Some(input) = self.local_input()? {
+            return Ok(input);
+        }
+        if self.should_fetch_input(force)? {
+            let input = http::get_input_async(self).await?;
+            self.store_input(input)?;
+        }
+        Ok(self.input.clone().unwrap())
+    }
+
+    /// Get input data given locally, in a file provided on the CLI, or piped
+    fn local_input(&self) -> Result<Option<String>, Error> {
         // Input file provided on CLI, read it
         if let Some(file) = &self.input_file {
-            return Ok(read_to_string(file)?.trim().to_string())
+            return Ok(Some(read_to_string(file)?.trim().to_string()))
         }
 
         // We are piped, read the piped data
@@ -178,37 +426,150 @@ impl Aoc {
             let stdin = io::stdin();
 
             let data = stdin.lock().lines()
-                .flatten()
+                .map_while(Result::ok)
                 .fold(String::new(), |mut acc, line| {
                     acc.push_str(&format!("{}\n", line));
                     acc
                 });
 
-            return Ok(data);
+            return Ok(Some(data));
         }
 
-        // Get input data from adventofcode.com
-        if self.input.is_none() || force {
-            let input = http::get_input(self)?;
-            self.input = Some(input);
-            self.write()?;
+        Ok(None)
+    }
+
+    fn should_fetch_input(&self, force: bool) -> Result<bool, Error> {
+        // also replace any error page which may have been cached by an
+        // older version
+        let poisoned = self.input.as_deref().is_some_and(http::is_error_page);
+        if self.input.is_some() && !force && !poisoned {
+            return Ok(false);
+        }
+        if let Release::Pending(_) = self.get_time_until_release()? {
+            return Err(Error::NotYetUnlocked);
         }
+        Ok(true)
+    }
 
-        Ok(self.input.clone().unwrap())
+    fn store_input(&mut self, input: String) -> Result<(), Error> {
+        self.input = Some(input);
+        self.write()
     }
 
     /// Submit the solution
+    ///
+    /// Answers known to be wrong from previous submissions are refused
+    /// without being submitted, see [`Aoc::check_answer`]. Otherwise, if the
+    /// wait imposed after a previous submission hasn't yet passed,
+    /// [`Error::Cooldown`] is returned.
     #[cfg(feature = "html_parsing")]
-    pub fn submit(&mut self, solution: &str) -> Result<String, Error> {
+    pub fn submit(&mut self, solution: &str) -> Result<SubmitOutcome, Error> {
+        self.check_submit(solution)?;
         let resp = http::submit(self, solution)?;
-        if http::verify(&resp) {
-            self.solution.insert(self.level, solution.to_string());
+        let outcome = self.record_submission(solution, &resp)?;
+        if outcome.is_correct() {
             self.get_brief(true).ok(); // Update brief (force) to update stars
-            self.add_star();
-            self.advance().unwrap_or(());
-            self.write()?;
+            self.complete_level()?;
         }
-        Ok(resp)
+        Ok(outcome)
+    }
+
+    /// Submit the solution asynchronously, see [`Aoc::submit`]
+    ///
+    /// The submission is recorded in the same way as by the blocking API.
+    #[cfg(all(feature = "html_parsing", feature = "async"))]
+    pub async fn submit_async(&mut self, solution: &str) -> Result<SubmitOutcome, Error> {
+        self.check_submit(solution)?;
+        let resp = http::submit_async(self, solution).await?;
+        let outcome = self.record_submission(solution, &resp)?;
+        if outcome.is_correct() {
+            self.get_brief_async(true).await.ok(); // Update brief (force) to update stars
+            self.complete_level()?;
+        }
+        Ok(outcome)
+    }
+
+    #[cfg(feature = "html_parsing")]
+    fn check_submit(&self, solution: &str) -> Result<(), Error> {
+        // an answer which can never be sent is refused before any wait
+        self.check_answer(solution)?;
+        match self.cooldown_remaining() {
+            Some(remaining) => Err(Error::Cooldown { remaining }),
+            None => Ok(()),
+        }
+    }
+
+    /// Record the outcome of a submission from the server's response
+    #[cfg(feature = "html_parsing")]
+    fn record_submission(&mut self, solution: &str, resp: &str) -> Result<SubmitOutcome, Error> {
+        if let Some(wait) = submission::parse_cooldown(resp) {
+            let wait = chrono::Duration::from_std(wait)
+                .map_err(|e| Error::Parse(e.to_string()))?;
+            self.cooldown = Some(Utc::now() + wait);
+        }
+        let outcome = match SubmitOutcome::from_html(resp) {
+            SubmitOutcome::WrongLevel if self.solution.contains_key(&self.level) => {
+                SubmitOutcome::AlreadySolved
+            },
+            outcome => outcome,
+        };
+        self.submissions.push(Submission {
+            level: self.level,
+            answer: solution.to_string(),
+            timestamp: Utc::now(),
+            outcome: outcome.clone(),
+        });
+        if outcome.is_correct() {
+            self.solution.insert(self.level, solution.to_string());
+        }
+        self.write()?;
+        Ok(outcome)
+    }
+
+    #[cfg(feature = "html_parsing")]
+    fn complete_level(&mut self) -> Result<(), Error> {
+        self.add_star();
+        self.advance().unwrap_or(());
+        self.write()
+    }
+
+    /// Check an answer for the current level against previous submissions
+    ///
+    /// Fails if the answer was already submitted and found incorrect, or if
+    /// it is outside the bounds given by previous "too high" or "too low"
+    /// answers.
+    pub fn check_answer(&self, solution: &str) -> Result<(), Error> {
+        match submission::known_wrong(&self.submissions, self.level, solution) {
+            Some(reason) => Err(Error::KnownWrongAnswer(reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the time remaining before another answer may be submitted
+    pub fn cooldown_remaining(&self) -> Option<Duration> {
+        (self.cooldown? - Utc::now()).to_std()
+            .ok()
+            .filter(|d| !d.is_zero())
+    }
+
+    /// Get the history of submissions for a level
+    pub fn submissions_for(&self, level: Level) -> impl Iterator<Item = &Submission> {
+        self.submissions.iter().filter(move |s| s.level == level)
+    }
+
+    /// Set the number of stars gained, such as from the calendar, and the
+    /// level to match
+    ///
+    /// Returns whether the stars or level changed, no stars being recorded
+    /// the same as none gained.
+    pub fn sync_stars(&mut self, stars: u8) -> bool {
+        let level = if stars > 0 { Level::Second } else { Level::First };
+        if (self.stars.unwrap_or(0), self.level) == (stars, level) {
+            return false;
+        }
+        self.stars = Some(stars);
+        self.level = level;
+        true
     }
 
     #[cfg(feature = "html_parsing")]
@@ -220,6 +581,27 @@ impl Aoc {
         };
     }
 
+    /// Get a client for making requests, using the configured cookie, base
+    /// URL, transport and throttle
+    pub fn client(&self) -> Client {
+        let throttle = match (&self.throttle, self.on_throttle) {
+            (Some(t), Some(callback)) => Some(t.clone().on_wait(callback)),
+            (t, _) => t.clone(),
+        };
+        let mut client = Client::new(&self.cookie).throttle(throttle);
+        if let Some(url) = &self.base_url {
+            client = client.base_url(url);
+        }
+        if let Some(transport) = &self.transport {
+            client = client.transport(transport.clone());
+        }
+        #[cfg(feature = "async")]
+        if let Some(transport) = &self.async_transport {
+            client = client.async_transport(transport.clone());
+        }
+        client
+    }
+
     /// get a JSON representation for the AoC problem
     pub fn to_json(&self) -> Result<String, Error> {
         Ok(serde_json::to_string_pretty(self)?)
@@ -256,7 +638,7 @@ impl Aoc {
     pub fn advance(&mut self) -> Result<(), Error> {
         match self.level {
             Level::First => { self.level = Level::Second; Ok(()) },
-            Level::Second => bail!("already on part 2"),
+            Level::Second => Err(Error::LastLevel),
         }
     }
 
@@ -268,8 +650,8 @@ impl Aoc {
         }
     }
 
-    fn get_default_cookie_path(&self) -> Result<PathBuf, Error> {
-        let p = PathBuf::from("./.aocf/cookie");
+    fn get_profile_dir(&self) -> Result<PathBuf, Error> {
+        let p = profile::dir(self.profile.as_deref())?;
         if let Ok(r) = find_root() {
             Ok(r.join(p))
         } else {
@@ -279,27 +661,27 @@ impl Aoc {
 
     fn get_default_cache_path(&self) -> Result<PathBuf, Error> {
         if let (Some(y), Some(d)) = (self.year, self.day) {
-            let p = PathBuf::from(&format!("./.aocf/cache/aoc{}_{:02}.json", y, d));
-            if let Ok(r) = find_root() {
-                Ok(r.join(p))
-            } else {
-                Ok(p)
-            }
+            Ok(self.get_profile_dir()?.join(format!("cache/aoc{}_{:02}.json", y, d)))
         } else {
-            bail!("day or year not set");
+            Err(Error::DayOrYearNotSet)
         }
     }
 
-    /// Get time until release
-    pub fn get_time_until_release() {
-
+    /// Get the time until the puzzle for the current year and day is released
+    pub fn get_time_until_release(&self) -> Result<Release, Error> {
+        if let (Some(y), Some(d)) = (self.year, self.day) {
+            Ok(release::release_status(y, d, Utc::now()))
+        } else {
+            Err(Error::DayOrYearNotSet)
+        }
     }
 }
 
 /// Get an ordered hashmap representation when serialising
-fn ordered_map<S>(value: &HashMap<Level, String>, serializer: S) -> Result<S::Ok, S::Error>
+fn ordered_map<S, V>(value: &HashMap<Level, V>, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
+    V: Serialize,
 {
     let ordered: BTreeMap<_, _> = value.iter().collect();
     ordered.serialize(serializer)
@@ -324,7 +706,7 @@ pub fn find_root() -> Result<PathBuf, Error> {
 
     match conf_dir {
         Some(dir) => Ok(dir.to_path_buf()),
-        None => bail!("no configuration found, maybe you need to run `aocf init`"),
+        None => Err(Error::NoConfiguration),
     }
 }
 
@@ -335,6 +717,83 @@ mod tests {
     use std::env;
     use std::fs;
 
+    #[test]
+    fn test_load_without_submissions() {
+        let json = r#"{"year":2020,"day":1,"level":"second","title":null,"stars":1,"solution":{"first":"42"},"input":null,"brief":{}}"#;
+        let aoc = Aoc::from_json(json).unwrap();
+        assert_eq!(aoc.level, Level::Second);
+        assert!(aoc.submissions.is_empty());
+    }
+
+    #[test]
+    fn test_keep_examples() {
+        let json = r#"{"year":2020,"day":1,"level":"first","title":null,"stars":null,"solution":{},"input":null,"brief":{},"examples":{"first":[{"input":"1721","answer":"514579"}]}}"#;
+        let aoc = Aoc::from_json(json).unwrap();
+        let aoc = Aoc::from_json(&aoc.to_json().unwrap()).unwrap();
+        let example = Example { input: "1721".to_string(), answer: Some("514579".to_string()) };
+        assert_eq!(aoc.examples.get(&Level::First), Some(&vec![example]));
+    }
+
+    #[cfg(feature = "html_parsing")]
+    #[test]
+    fn test_check_submit() {
+        let mut aoc = Aoc::new().year(Some(2020)).day(Some(1));
+        aoc.submissions.push(Submission {
+            level: Level::First,
+            answer: "500".to_string(),
+            timestamp: Utc::now(),
+            outcome: SubmitOutcome::Incorrect { hint: Some(Hint::TooHigh) },
+        });
+        aoc.cooldown = Some(Utc::now() + chrono::Duration::seconds(60));
+
+        assert!(matches!(aoc.check_submit("612"), Err(Error::KnownWrongAnswer(_))));
+        assert!(matches!(aoc.check_submit("499"), Err(Error::Cooldown { .. })));
+
+        aoc.cooldown = None;
+        assert!(aoc.check_submit("499").is_ok());
+    }
+
+    #[test]
+    fn test_sync_stars() {
+        // a day not yet cached, with no stars on the calendar
+        let mut aoc = Aoc::new().year(Some(2020)).day(Some(1));
+        assert!(!aoc.sync_stars(0));
+        assert_eq!((aoc.stars, aoc.level), (None, Level::First));
+
+        assert!(aoc.sync_stars(1));
+        assert_eq!((aoc.stars, aoc.level), (Some(1), Level::Second));
+        assert!(!aoc.sync_stars(1));
+        assert!(aoc.sync_stars(2));
+        assert_eq!((aoc.stars, aoc.level), (Some(2), Level::Second));
+    }
+
+    #[test]
+    fn test_secret_store() {
+        let store = Arc::new(secret::MemoryStore::new());
+        store.set("53616c746564").unwrap();
+        let aoc = Aoc::new()
+            .parse_cli(false)
+            .secret_store(store.clone())
+            .init()
+            .unwrap();
+        assert_eq!(aoc.cookie, "53616c746564");
+    }
+
+    #[test]
+    fn test_profile_paths() {
+        assert!(matches!(Aoc::new().get_default_cache_path(), Err(Error::DayOrYearNotSet)));
+
+        let aoc = Aoc::new().year(Some(2020)).day(Some(1));
+        assert!(aoc.get_default_cache_path().unwrap().ends_with(".aocf/cache/aoc2020_01.json"));
+        assert!(aoc.get_profile_dir().unwrap().ends_with(".aocf"));
+
+        let aoc = aoc.profile(Some("alice"));
+        assert!(aoc.get_default_cache_path().unwrap().ends_with(".aocf/profiles/alice/cache/aoc2020_01.json"));
+        assert!(aoc.get_profile_dir().unwrap().ends_with(".aocf/profiles/alice"));
+
+        assert!(aoc.profile(Some("../alice")).parse_cli(false).init().is_err());
+    }
+
     #[test]
     fn test_find_root() {
         let tmp = tempdir().unwrap();
@@ -343,13 +802,13 @@ mod tests {
         fs::create_dir_all(&tmp_sub).unwrap();
 
         env::set_current_dir(tmp_path).unwrap();
-        assert!(find_root().is_err());
+        assert!(matches!(find_root(), Err(Error::NoConfiguration)));
         fs::create_dir(tmp_path.join(".aocf")).unwrap();
         assert!(find_root().is_err());
         File::create(tmp_path.join(".aocf/config")).unwrap();
         assert!(find_root().is_ok());
         env::set_current_dir(tmp_sub).unwrap();
-        if cfg!(linux) || cfg!(windows) {
+        if cfg!(target_os = "linux") || cfg!(windows) {
             /* Very strange result on macos...
              *
              * ---- tests::test_find_root stdout ----
@@ -0,0 +1,70 @@
+//! Named account profiles
+//!
+//! Inputs differ between accounts, so each profile has its own session
+//! cookie and cache, kept in `.aocf/profiles/<name>`. The default profile
+//! keeps these directly in `.aocf`.
+use crate::Error;
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+/// Get the directory holding a profile's cookie and cache, relative to the
+/// root of an aocf repository
+pub fn dir(profile: Option<&str>) -> Result<PathBuf, Error> {
+    match profile {
+        None => Ok(PathBuf::from(".aocf")),
+        Some(name) if is_valid(name) => Ok(Path::new(".aocf/profiles").join(name)),
+        Some(name) => Err(Error::Config(format!("invalid profile name: {:?}", name))),
+    }
+}
+
+/// Whether a profile name can be used as a single directory name
+fn is_valid(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\'])
+}
+
+/// List the named profiles in an aocf repository, in order
+pub fn list(root: impl AsRef<Path>) -> Result<Vec<String>, Error> {
+    let profiles_dir = root.as_ref().join(".aocf/profiles");
+    if !profiles_dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut profiles: Vec<_> = read_dir(profiles_dir)?
+        .flat_map(|r| r.map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .filter_map(|p| p.file_name()?.to_str().map(String::from))
+        .collect();
+
+    profiles.sort();
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::create_dir_all;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_dir() {
+        assert_eq!(dir(None).unwrap(), PathBuf::from(".aocf"));
+        assert_eq!(dir(Some("alice")).unwrap(), PathBuf::from(".aocf/profiles/alice"));
+        for name in &["", "..", "a/b", "a\\b"] {
+            assert!(dir(Some(name)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_list() {
+        let tmp = tempdir().unwrap();
+        assert!(list(tmp.path()).unwrap().is_empty());
+
+        create_dir_all(tmp.path().join(".aocf/profiles/bob")).unwrap();
+        create_dir_all(tmp.path().join(".aocf/profiles/alice")).unwrap();
+        std::fs::write(tmp.path().join(".aocf/profiles/stray"), "").unwrap();
+        assert_eq!(list(tmp.path()).unwrap(), vec!["alice", "bob"]);
+    }
+}
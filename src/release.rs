@@ -0,0 +1,130 @@
+//! Puzzle release clock
+//!
+//! Puzzles unlock at midnight EST (UTC-5) on each day of an event, which runs
+//! from the 1st of December. Events up to 2024 have 25 days, later events
+//! have 12.
+
+use chrono::{DateTime, Datelike, FixedOffset, TimeZone, Utc};
+use std::time::Duration;
+
+/// The first year in which an event was held
+pub const FIRST_EVENT: i32 = 2015;
+
+/// Offset of the timezone puzzles are released in, in seconds (UTC-5)
+const RELEASE_OFFSET: i32 = -5 * 3600;
+
+/// Release status of a puzzle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Release {
+    /// The puzzle unlocks after the given duration
+    Pending(Duration),
+    /// The puzzle is already available
+    Released,
+    /// The day is not part of an event calendar
+    NotInEvent,
+}
+
+fn release_tz() -> FixedOffset {
+    FixedOffset::east_opt(RELEASE_OFFSET).unwrap()
+}
+
+/// Get the number of days in the event for a given year
+pub fn event_days(year: i32) -> Option<u32> {
+    match year {
+        y if y < FIRST_EVENT => None,
+        y if y <= 2024 => Some(25),
+        _ => Some(12),
+    }
+}
+
+/// Get the time at which the puzzle for a given year and day unlocks
+pub fn release_time(year: i32, day: u32) -> Option<DateTime<Utc>> {
+    match event_days(year) {
+        Some(n) if (1..=n).contains(&day) => release_tz()
+            .with_ymd_and_hms(year, 12, day, 0, 0, 0)
+            .single()
+            .map(|t| t.with_timezone(&Utc)),
+        _ => None,
+    }
+}
+
+/// Get the release status of a puzzle at the given time
+pub fn release_status(year: i32, day: u32, now: DateTime<Utc>) -> Release {
+    match release_time(year, day) {
+        Some(t) if t > now => Release::Pending((t - now).to_std().unwrap_or_default()),
+        Some(_) => Release::Released,
+        None => Release::NotInEvent,
+    }
+}
+
+/// Get the year and day of the puzzle released on the current day of an
+/// event, if an event is running at the given time
+pub fn current_puzzle(now: DateTime<Utc>) -> Option<(i32, u32)> {
+    let local = now.with_timezone(&release_tz());
+    match event_days(local.year()) {
+        Some(n) if local.month() == 12 && local.day() <= n => Some((local.year(), local.day())),
+        _ => None,
+    }
+}
+
+/// Get the year and day of the next puzzle to be released after the given
+/// time, along with its release time
+pub fn next_release(now: DateTime<Utc>) -> (i32, u32, DateTime<Utc>) {
+    let local = now.with_timezone(&release_tz());
+    let (year, day) = match (local.month(), event_days(local.year())) {
+        (12, Some(n)) if local.day() < n => (local.year(), local.day() + 1),
+        (12, _) => (local.year() + 1, 1),
+        (_, Some(_)) => (local.year(), 1),
+        (_, None) => (FIRST_EVENT, 1),
+    };
+    // all events have at least one day, from the first event onwards
+    (year, day, release_time(year, day).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_release_time() {
+        assert_eq!(release_time(2020, 1), Some(utc(2020, 12, 1, 5, 0)));
+        assert_eq!(release_time(2020, 25), Some(utc(2020, 12, 25, 5, 0)));
+        assert_eq!(release_time(2020, 26), None);
+        assert_eq!(release_time(2025, 12), Some(utc(2025, 12, 12, 5, 0)));
+        assert_eq!(release_time(2025, 13), None);
+        assert_eq!(release_time(2014, 1), None);
+        assert_eq!(release_time(2020, 0), None);
+    }
+
+    #[test]
+    fn test_release_status() {
+        let now = utc(2020, 12, 1, 4, 30);
+        assert_eq!(release_status(2020, 1, now), Release::Pending(Duration::from_secs(1800)));
+        assert_eq!(release_status(2020, 1, utc(2020, 12, 1, 5, 0)), Release::Released);
+        assert_eq!(release_status(2019, 25, now), Release::Released);
+        assert_eq!(release_status(2025, 20, now), Release::NotInEvent);
+    }
+
+    #[test]
+    fn test_current_puzzle() {
+        // still the 1st of December in UTC-5
+        assert_eq!(current_puzzle(utc(2020, 12, 2, 4, 59)), Some((2020, 1)));
+        assert_eq!(current_puzzle(utc(2020, 12, 2, 5, 0)), Some((2020, 2)));
+        assert_eq!(current_puzzle(utc(2020, 12, 1, 4, 59)), None);
+        assert_eq!(current_puzzle(utc(2020, 12, 26, 12, 0)), None);
+        assert_eq!(current_puzzle(utc(2025, 12, 13, 12, 0)), None);
+    }
+
+    #[test]
+    fn test_next_release() {
+        assert_eq!(next_release(utc(2020, 6, 1, 0, 0)), (2020, 1, utc(2020, 12, 1, 5, 0)));
+        assert_eq!(next_release(utc(2020, 12, 1, 4, 0)), (2020, 1, utc(2020, 12, 1, 5, 0)));
+        assert_eq!(next_release(utc(2020, 12, 1, 5, 0)), (2020, 2, utc(2020, 12, 2, 5, 0)));
+        assert_eq!(next_release(utc(2020, 12, 26, 5, 0)), (2021, 1, utc(2021, 12, 1, 5, 0)));
+        assert_eq!(next_release(utc(2025, 12, 12, 5, 0)), (2026, 1, utc(2026, 12, 1, 5, 0)));
+    }
+}
@@ -0,0 +1,351 @@
+//! Storage for session cookies
+//!
+//! By default the session cookie is kept in plaintext, in `cookie` in a
+//! profile's directory, and is refused if other users can read it. It may
+//! instead be kept in the freedesktop Secret Service, leaving `cookie.keyring`
+//! to record this, or with the `encrypted_cookie` feature, in `cookie.enc`,
+//! encrypted with a passphrase. The passphrase may be given when opening the
+//! store, or otherwise by the `AOCF_PASSPHRASE` environment variable. The
+//! storage used is found from which of these files exists.
+use crate::Error;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "encrypted_cookie")]
+mod encrypted;
+
+#[cfg(feature = "encrypted_cookie")]
+pub use encrypted::EncryptedFile;
+
+/// Environment variable giving the passphrase for an encrypted cookie file
+pub const PASSPHRASE_VAR: &str = "AOCF_PASSPHRASE";
+
+/// Somewhere a session cookie may be stored
+pub trait SecretStore: fmt::Debug + Send + Sync {
+    /// Get the stored cookie
+    fn get(&self) -> Result<String, Error>;
+    /// Store a cookie, replacing any already stored
+    fn set(&self, cookie: &str) -> Result<(), Error>;
+}
+
+impl<T: SecretStore + ?Sized> SecretStore for Arc<T> {
+    fn get(&self) -> Result<String, Error> {
+        (**self).get()
+    }
+
+    fn set(&self, cookie: &str) -> Result<(), Error> {
+        (**self).set(cookie)
+    }
+}
+
+/// How a profile's session cookie is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Storage {
+    /// In a plaintext file, which only the user may read
+    Plaintext,
+    /// In the freedesktop Secret Service
+    SecretService,
+    /// In a file encrypted with a passphrase
+    EncryptedFile,
+}
+
+impl Storage {
+    const ALL: [Storage; 3] = [Storage::Plaintext, Storage::SecretService, Storage::EncryptedFile];
+
+    /// Get the name of the file in a profile's directory which records a
+    /// cookie stored this way
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Storage::Plaintext => "cookie",
+            Storage::SecretService => "cookie.keyring",
+            Storage::EncryptedFile => "cookie.enc",
+        }
+    }
+
+    /// Find how the cookie in a profile's directory is stored, if there is
+    /// one
+    pub fn detect(dir: impl AsRef<Path>) -> Option<Self> {
+        Self::ALL.iter()
+            .copied()
+            .find(|s| dir.as_ref().join(s.file_name()).is_file())
+    }
+}
+
+/// Open the store for the cookie in a profile's directory
+///
+/// The passphrase is only used for an encrypted cookie file, and defaults to
+/// the value of [`PASSPHRASE_VAR`].
+#[cfg_attr(not(feature = "encrypted_cookie"), allow(unused_variables))]
+pub fn open(
+    dir: impl AsRef<Path>,
+    profile: Option<&str>,
+    storage: Storage,
+    passphrase: Option<&str>,
+) -> Result<Box<dyn SecretStore>, Error> {
+    let path = dir.as_ref().join(storage.file_name());
+    match storage {
+        Storage::Plaintext => Ok(Box::new(PlaintextFile::new(path))),
+        Storage::SecretService => Ok(Box::new(SecretService::new(profile))),
+        #[cfg(feature = "encrypted_cookie")]
+        Storage::EncryptedFile => {
+            let passphrase = match passphrase {
+                Some(p) => p.to_string(),
+                None => std::env::var(PASSPHRASE_VAR).map_err(|_| {
+                    Error::Secret(format!("the cookie is encrypted, set {} to its passphrase", PASSPHRASE_VAR))
+                })?,
+            };
+            Ok(Box::new(EncryptedFile::new(path, &passphrase)))
+        },
+        #[cfg(not(feature = "encrypted_cookie"))]
+        Storage::EncryptedFile => {
+            Err(Error::Secret("built without support for encrypted cookies".to_string()))
+        },
+    }
+}
+
+/// Open the store for the cookie in a profile's directory, however it's
+/// stored, defaulting to plaintext
+pub fn resolve(
+    dir: impl AsRef<Path>,
+    profile: Option<&str>,
+    passphrase: Option<&str>,
+) -> Result<Box<dyn SecretStore>, Error> {
+    let storage = Storage::detect(&dir).unwrap_or(Storage::Plaintext);
+    open(dir, profile, storage, passphrase)
+}
+
+/// Store the cookie for a profile, removing the cookie if it was stored
+/// another way
+pub fn set_cookie(
+    dir: impl AsRef<Path>,
+    profile: Option<&str>,
+    storage: Storage,
+    passphrase: Option<&str>,
+    cookie: &str,
+) -> Result<(), Error> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    open(dir, profile, storage, passphrase)?.set(cookie)?;
+
+    if storage == Storage::SecretService {
+        let note = format!("the session cookie is stored in the Secret Service, {}\n", SecretService::new(profile).label());
+        fs::write(dir.join(storage.file_name()), note)?;
+    }
+
+    for other in Storage::ALL.iter().filter(|s| **s != storage) {
+        let path = dir.join(other.file_name());
+        if path.is_file() {
+            fs::remove_file(path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A cookie kept in a plaintext file
+#[derive(Debug, Clone)]
+pub struct PlaintextFile {
+    path: PathBuf,
+}
+
+impl PlaintextFile {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self { path: path.as_ref().to_path_buf() }
+    }
+
+    /// Refuse to use the file if other users can read it
+    #[cfg(unix)]
+    fn check_permissions(&self) -> Result<(), Error> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = fs::metadata(&self.path)?.permissions().mode();
+        if mode & 0o004 != 0 {
+            return Err(Error::Secret(format!(
+                "{} can be read by other users, restrict it with `chmod 600 {}`",
+                self.path.display(), self.path.display(),
+            )));
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn check_permissions(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl SecretStore for PlaintextFile {
+    fn get(&self) -> Result<String, Error> {
+        self.check_permissions()?;
+        Ok(fs::read_to_string(&self.path)?.trim().to_string())
+    }
+
+    fn set(&self, cookie: &str) -> Result<(), Error> {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&self.path)?;
+        // the mode is only used when the file is created
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(file.write_all(cookie.as_bytes())?)
+    }
+}
+
+/// A cookie kept in the freedesktop Secret Service, e.g. GNOME Keyring or
+/// KWallet, using `secret-tool`
+///
+/// The cookie is stored with the attributes `application aocf profile
+/// <name>`, the default profile being named `default`.
+#[derive(Debug, Clone)]
+pub struct SecretService {
+    profile: String,
+}
+
+impl SecretService {
+    pub fn new(profile: Option<&str>) -> Self {
+        Self { profile: profile.unwrap_or("default").to_string() }
+    }
+
+    fn attributes(&self) -> [&str; 4] {
+        ["application", "aocf", "profile", &self.profile]
+    }
+
+    fn label(&self) -> String {
+        format!("with attributes {}", self.attributes().join(" "))
+    }
+}
+
+fn secret_tool_error(e: std::io::Error) -> Error {
+    Error::Secret(format!("can't run secret-tool: {}", e))
+}
+
+impl SecretStore for SecretService {
+    fn get(&self) -> Result<String, Error> {
+        let output = Command::new("secret-tool")
+            .arg("lookup")
+            .args(self.attributes())
+            .stderr(Stdio::null())
+            .output()
+            .map_err(secret_tool_error)?;
+
+        match String::from_utf8_lossy(&output.stdout).trim() {
+            "" => Err(Error::Secret(format!("no cookie found in the Secret Service {}", self.label()))),
+            s => Ok(s.to_string()),
+        }
+    }
+
+    fn set(&self, cookie: &str) -> Result<(), Error> {
+        let mut child = Command::new("secret-tool")
+            .arg("store")
+            .arg(format!("--label=aocf session cookie ({})", self.profile))
+            .args(self.attributes())
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(secret_tool_error)?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(cookie.as_bytes())?;
+        }
+
+        match child.wait()?.success() {
+            true => Ok(()),
+            false => Err(Error::Secret("secret-tool failed to store the cookie".to_string())),
+        }
+    }
+}
+
+/// A cookie kept in memory, a stand in for a keyring when testing
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    cookie: Mutex<Option<String>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SecretStore for MemoryStore {
+    fn get(&self) -> Result<String, Error> {
+        self.cookie.lock().unwrap().clone()
+            .ok_or_else(|| Error::Secret("no cookie stored".to_string()))
+    }
+
+    fn set(&self, cookie: &str) -> Result<(), Error> {
+        *self.cookie.lock().unwrap() = Some(cookie.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_plaintext() {
+        let tmp = tempdir().unwrap();
+        set_cookie(tmp.path(), None, Storage::Plaintext, None, "53616c746564").unwrap();
+        assert_eq!(Storage::detect(tmp.path()), Some(Storage::Plaintext));
+        assert_eq!(resolve(tmp.path(), None, None).unwrap().get().unwrap(), "53616c746564");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let path = tmp.path().join("cookie");
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+            assert!(matches!(resolve(tmp.path(), None, None).unwrap().get(), Err(Error::Secret(_))));
+
+            // storing the cookie again restricts the file
+            set_cookie(tmp.path(), None, Storage::Plaintext, None, "53616c746564").unwrap();
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        let tmp = tempdir().unwrap();
+        assert_eq!(Storage::detect(tmp.path()), None);
+        fs::write(tmp.path().join("cookie.keyring"), "").unwrap();
+        assert_eq!(Storage::detect(tmp.path()), Some(Storage::SecretService));
+
+        // storing the cookie another way removes the record of the old one
+        set_cookie(tmp.path(), None, Storage::Plaintext, None, "53616c746564").unwrap();
+        assert_eq!(Storage::detect(tmp.path()), Some(Storage::Plaintext));
+        assert!(!tmp.path().join("cookie.keyring").exists());
+    }
+
+    #[cfg(feature = "encrypted_cookie")]
+    #[test]
+    fn test_passphrase() {
+        let tmp = tempdir().unwrap();
+        EncryptedFile::new(tmp.path().join("cookie.enc"), "hunter2")
+            .iterations(1)
+            .set("53616c746564")
+            .unwrap();
+        assert_eq!(Storage::detect(tmp.path()), Some(Storage::EncryptedFile));
+        assert_eq!(resolve(tmp.path(), None, Some("hunter2")).unwrap().get().unwrap(), "53616c746564");
+        assert!(matches!(resolve(tmp.path(), None, Some("wrong")).unwrap().get(), Err(Error::Secret(_))));
+    }
+
+    #[test]
+    fn test_memory_store() {
+        let store = MemoryStore::new();
+        assert!(store.get().is_err());
+        store.set("53616c746564").unwrap();
+        assert_eq!(store.get().unwrap(), "53616c746564");
+    }
+}
@@ -0,0 +1,119 @@
+//! A cookie file encrypted with a passphrase
+//!
+//! The cookie is encrypted with AES-256-GCM, with a key derived from the
+//! passphrase using PBKDF2-HMAC-SHA256, and a random salt and nonce. These
+//! are kept with the ciphertext in a JSON file, hex encoded.
+use super::SecretStore;
+use crate::{Error, hex};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, rand_core::RngCore};
+use aes_gcm::{Aes256Gcm, Nonce};
+use sha2::Sha256;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Sealed {
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// A cookie kept in a file encrypted with a passphrase
+pub struct EncryptedFile {
+    path: PathBuf,
+    passphrase: String,
+    /// Iterations used to derive the key when storing a cookie
+    iterations: u32,
+}
+
+// the passphrase is left out
+impl fmt::Debug for EncryptedFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EncryptedFile").field("path", &self.path).finish()
+    }
+}
+
+impl EncryptedFile {
+    pub fn new(path: impl AsRef<Path>, passphrase: &str) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            passphrase: passphrase.to_string(),
+            iterations: ITERATIONS,
+        }
+    }
+
+    /// Use fewer iterations when storing a cookie, to keep tests quick
+    #[cfg(test)]
+    pub(super) fn iterations(mut self, iterations: u32) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    fn cipher(&self, salt: &[u8], iterations: u32) -> Aes256Gcm {
+        let mut key = [0; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(self.passphrase.as_bytes(), salt, iterations, &mut key);
+        Aes256Gcm::new(&key.into())
+    }
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, Error> {
+    hex::decode(hex).ok_or_else(|| Error::Secret("invalid encrypted cookie file".to_string()))
+}
+
+impl SecretStore for EncryptedFile {
+    fn get(&self) -> Result<String, Error> {
+        let sealed: Sealed = serde_json::from_str(&fs::read_to_string(&self.path)?)?;
+        let nonce = decode_hex(&sealed.nonce)?;
+        if nonce.len() != 12 {
+            return Err(Error::Secret("invalid encrypted cookie file".to_string()));
+        }
+
+        let plaintext = self.cipher(&decode_hex(&sealed.salt)?, sealed.iterations)
+            .decrypt(Nonce::from_slice(&nonce), decode_hex(&sealed.ciphertext)?.as_slice())
+            .map_err(|_| Error::Secret("can't decrypt the cookie, the passphrase may be wrong".to_string()))?;
+
+        String::from_utf8(plaintext)
+            .map_err(|_| Error::Secret("the decrypted cookie isn't valid UTF-8".to_string()))
+    }
+
+    fn set(&self, cookie: &str) -> Result<(), Error> {
+        let mut salt = [0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+        let ciphertext = self.cipher(&salt, self.iterations)
+            .encrypt(&nonce, cookie.as_bytes())
+            .map_err(|_| Error::Secret("can't encrypt the cookie".to_string()))?;
+
+        let sealed = Sealed {
+            iterations: self.iterations,
+            salt: hex::encode(&salt),
+            nonce: hex::encode(&nonce),
+            ciphertext: hex::encode(&ciphertext),
+        };
+        Ok(fs::write(&self.path, serde_json::to_string(&sealed)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_encrypted_file() {
+        let tmp = tempdir().unwrap();
+        let path = tmp.path().join("cookie.enc");
+        let store = EncryptedFile::new(&path, "hunter2").iterations(1);
+        store.set("53616c746564").unwrap();
+
+        assert!(!fs::read_to_string(&path).unwrap().contains("53616c746564"));
+        assert_eq!(store.get().unwrap(), "53616c746564");
+        assert!(EncryptedFile::new(&path, "wrong").get().is_err());
+    }
+}
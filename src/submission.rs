@@ -0,0 +1,240 @@
+//! Submission results and history
+use crate::Level;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use std::fmt;
+use std::time::Duration;
+
+/// Hint given by the server for an incorrect answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Hint {
+    TooHigh,
+    TooLow,
+}
+
+/// The outcome of submitting an answer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmitOutcome {
+    /// The answer was correct
+    Correct,
+    /// The answer was incorrect, possibly with a hint on how it was wrong
+    Incorrect { hint: Option<Hint> },
+    /// An answer was submitted too recently, try again after the given wait
+    RateLimited { wait: Duration },
+    /// The puzzle has already been solved
+    AlreadySolved,
+    /// The answer was submitted for a level which isn't currently open
+    WrongLevel,
+    /// The response couldn't be interpreted, contains the response text
+    Unknown(String),
+}
+
+impl SubmitOutcome {
+    /// Parse the outcome from the HTML of a submission response
+    pub fn from_html(html: &str) -> Self {
+        let text = response_text(html);
+
+        if text.contains("That's the right answer!") {
+            Self::Correct
+        } else if text.contains("That's not the right answer") {
+            let hint = if text.contains("your answer is too high") {
+                Some(Hint::TooHigh)
+            } else if text.contains("your answer is too low") {
+                Some(Hint::TooLow)
+            } else {
+                None
+            };
+            Self::Incorrect { hint }
+        } else if text.contains("You gave an answer too recently") {
+            Self::RateLimited { wait: parse_wait(&text).unwrap_or_default() }
+        } else if text.contains("You don't seem to be solving the right level") {
+            Self::WrongLevel
+        } else {
+            Self::Unknown(text)
+        }
+    }
+
+    pub fn is_correct(&self) -> bool {
+        *self == Self::Correct
+    }
+}
+
+impl fmt::Display for SubmitOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Correct => write!(f, "That's the right answer!"),
+            Self::Incorrect { hint: Some(Hint::TooHigh) } => write!(f, "That's not the right answer, your answer is too high."),
+            Self::Incorrect { hint: Some(Hint::TooLow) } => write!(f, "That's not the right answer, your answer is too low."),
+            Self::Incorrect { hint: None } => write!(f, "That's not the right answer."),
+            Self::RateLimited { wait } => write!(f, "You gave an answer too recently, {}s left to wait.", wait.as_secs()),
+            Self::AlreadySolved => write!(f, "This puzzle has already been solved."),
+            Self::WrongLevel => write!(f, "You don't seem to be solving the right level. Did you already complete it?"),
+            Self::Unknown(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// A record of an answer submitted for a puzzle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Submission {
+    pub level: Level,
+    pub answer: String,
+    pub timestamp: DateTime<Utc>,
+    pub outcome: SubmitOutcome,
+}
+
+/// Check an answer against previous submissions for a level, giving the
+/// reason it is known to be wrong, if it is
+pub(crate) fn known_wrong(history: &[Submission], level: Level, answer: &str) -> Option<String> {
+    let answer = answer.trim();
+    let value = answer.parse::<i128>().ok();
+
+    history.iter()
+        .filter(|s| s.level == level)
+        .find_map(|s| {
+            let hint = match s.outcome {
+                SubmitOutcome::Incorrect { hint } => hint,
+                _ => return None,
+            };
+            if s.answer.trim() == answer {
+                return Some(format!("answer {} was already submitted and is incorrect", answer));
+            }
+            match (hint, value, s.answer.trim().parse::<i128>()) {
+                (Some(Hint::TooHigh), Some(v), Ok(bound)) if v >= bound => {
+                    Some(format!("answer {} is too high, {} was already too high", answer, bound))
+                },
+                (Some(Hint::TooLow), Some(v), Ok(bound)) if v <= bound => {
+                    Some(format!("answer {} is too low, {} was already too low", answer, bound))
+                },
+                _ => None,
+            }
+        })
+}
+
+/// Parse the time to wait before submitting again from a submission response
+#[cfg(feature = "html_parsing")]
+pub(crate) fn parse_cooldown(html: &str) -> Option<Duration> {
+    let text = response_text(html);
+    parse_wait(&text).or_else(|| parse_please_wait(&text))
+}
+
+/// Get the plain text of the main section of a response
+fn response_text(html: &str) -> String {
+    let main = Regex::new(r"(?s)<main>(.*?)</main>").unwrap();
+    let html = main.captures(html)
+        .and_then(|c| c.get(1))
+        .map_or(html, |m| m.as_str());
+    let tags = Regex::new(r"<[^>]*>").unwrap();
+    let text = tags.replace_all(html, "");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parse a wait time given as e.g. "You have 4m 32s left to wait"
+fn parse_wait(text: &str) -> Option<Duration> {
+    let regex = Regex::new(r"You have (?:(\d+)h )?(?:(\d+)m )?(\d+)s left to wait").unwrap();
+    let captures = regex.captures(text)?;
+    let secs = [(1, 3600), (2, 60), (3, 1)].iter()
+        .filter_map(|(i, unit)| Some(captures.get(*i)?.as_str().parse::<u64>().ok()? * unit))
+        .sum();
+    Some(Duration::from_secs(secs))
+}
+
+/// Parse a wait time given as e.g. "Please wait one minute before trying again"
+#[cfg(feature = "html_parsing")]
+fn parse_please_wait(text: &str) -> Option<Duration> {
+    let regex = Regex::new(r"(?i)please wait (\w+) minutes? before trying again").unwrap();
+    let minutes = regex.captures(text)?.get(1)?.as_str().to_lowercase();
+    let minutes = match minutes.as_str() {
+        "one" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "ten" => 10,
+        m => m.parse().ok()?,
+    };
+    Some(Duration::from_secs(minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(article: &str) -> String {
+        format!("<html><body><main>\n<article><p>{}</p></article>\n</main></body></html>", article)
+    }
+
+    #[test]
+    fn test_correct() {
+        let html = page("That's the right answer!  You are <span class=\"day-success\">one gold star</span> closer to saving Christmas.");
+        assert_eq!(SubmitOutcome::from_html(&html), SubmitOutcome::Correct);
+    }
+
+    #[test]
+    fn test_incorrect() {
+        let html = page("That's not the right answer; your answer is too high.  If you're stuck, make sure you're using the full input data. Please wait one minute before trying again. <a href=\"/2020/day/1\">[Return to Day 1]</a>");
+        assert_eq!(SubmitOutcome::from_html(&html), SubmitOutcome::Incorrect { hint: Some(Hint::TooHigh) });
+        let html = page("That's not the right answer; your answer is too low.");
+        assert_eq!(SubmitOutcome::from_html(&html), SubmitOutcome::Incorrect { hint: Some(Hint::TooLow) });
+        let html = page("That's not the right answer.  If you're stuck, make sure you're using the full input data.");
+        assert_eq!(SubmitOutcome::from_html(&html), SubmitOutcome::Incorrect { hint: None });
+    }
+
+    #[test]
+    fn test_rate_limited() {
+        let html = page("You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 4m 32s left to wait. <a href=\"/2020/day/1\">[Return to Day 1]</a>");
+        assert_eq!(SubmitOutcome::from_html(&html), SubmitOutcome::RateLimited { wait: Duration::from_secs(272) });
+        let html = page("You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 34s left to wait.");
+        assert_eq!(SubmitOutcome::from_html(&html), SubmitOutcome::RateLimited { wait: Duration::from_secs(34) });
+    }
+
+    #[test]
+    fn test_wrong_level() {
+        let html = page("You don't seem to be solving the right level.  Did you already complete it? <a href=\"/2020/day/1\">[Return to Day 1]</a>");
+        assert_eq!(SubmitOutcome::from_html(&html), SubmitOutcome::WrongLevel);
+    }
+
+    #[test]
+    #[cfg(feature = "html_parsing")]
+    fn test_cooldown() {
+        let html = page("That's not the right answer.  If you're stuck, make sure you're using the full input data. Please wait one minute before trying again.");
+        assert_eq!(parse_cooldown(&html), Some(Duration::from_secs(60)));
+        let html = page("That's not the right answer; your answer is too low.  Because you have guessed incorrectly 4 times on this puzzle, please wait 5 minutes before trying again.");
+        assert_eq!(parse_cooldown(&html), Some(Duration::from_secs(300)));
+        let html = page("You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 1m 5s left to wait.");
+        assert_eq!(parse_cooldown(&html), Some(Duration::from_secs(65)));
+        let html = page("That's the right answer!");
+        assert_eq!(parse_cooldown(&html), None);
+    }
+
+    fn submission(level: Level, answer: &str, outcome: SubmitOutcome) -> Submission {
+        Submission { level, answer: answer.to_string(), timestamp: Utc::now(), outcome }
+    }
+
+    #[test]
+    fn test_known_wrong() {
+        let history = vec![
+            submission(Level::First, "500", SubmitOutcome::Incorrect { hint: Some(Hint::TooHigh) }),
+            submission(Level::First, "100", SubmitOutcome::Incorrect { hint: Some(Hint::TooLow) }),
+            submission(Level::First, "abc", SubmitOutcome::Incorrect { hint: None }),
+            submission(Level::First, "300", SubmitOutcome::RateLimited { wait: Duration::from_secs(30) }),
+        ];
+        assert!(known_wrong(&history, Level::First, "500").is_some());
+        assert!(known_wrong(&history, Level::First, "612").is_some());
+        assert!(known_wrong(&history, Level::First, "100").is_some());
+        assert!(known_wrong(&history, Level::First, "-4").is_some());
+        assert!(known_wrong(&history, Level::First, " abc ").is_some());
+        assert!(known_wrong(&history, Level::First, "300").is_none());
+        assert!(known_wrong(&history, Level::First, "499").is_none());
+        assert!(known_wrong(&history, Level::First, "def").is_none());
+        assert!(known_wrong(&history, Level::Second, "612").is_none());
+    }
+
+    #[test]
+    fn test_unknown() {
+        let html = page("Something <em>else</em>.");
+        assert_eq!(SubmitOutcome::from_html(&html), SubmitOutcome::Unknown("Something else.".to_string()));
+    }
+}
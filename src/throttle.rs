@@ -0,0 +1,232 @@
+//! Client side request throttling
+//!
+//! The time of the last request is kept in a state file, so that a minimum
+//! interval between requests is enforced across processes, as well as within
+//! a process. The state file is locked while it's updated, by creating a lock
+//! file next to it, so processes take turns.
+use crate::{Error, find_root};
+use std::ffi::OsString;
+use std::fs::{OpenOptions, metadata, read_to_string, remove_file, write};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Default minimum interval between requests
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Age after which a lock file is taken to have been left behind by a
+/// process which exited while holding it
+const STALE_LOCK: Duration = Duration::from_secs(10);
+
+/// Time of the last request made by this process
+static LAST_REQUEST: Mutex<Option<SystemTime>> = Mutex::new(None);
+
+/// Enforces a minimum interval between requests
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    interval: Duration,
+    state_file: Option<PathBuf>,
+    /// Called with the delay before a request which has to wait
+    on_wait: Option<fn(Duration)>,
+}
+
+// the callback is left out, as functions can't be meaningfully compared
+impl PartialEq for Throttle {
+    fn eq(&self, other: &Self) -> bool {
+        self.interval == other.interval && self.state_file == other.state_file
+    }
+}
+
+impl Eq for Throttle {}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::new(DEFAULT_INTERVAL)
+    }
+}
+
+impl Throttle {
+    /// Create a throttle with the given minimum interval between requests
+    ///
+    /// The time of the last request is kept in `.aocf/last_request` if an
+    /// aocf root configuration exists, otherwise requests are only throttled
+    /// within the current process.
+    pub fn new(interval: Duration) -> Self {
+        let state_file = find_root().ok().map(|r| r.join(".aocf/last_request"));
+        Self { interval, state_file, on_wait: None }
+    }
+
+    /// Set the file to keep the time of the last request in
+    pub fn state_file(mut self, path: Option<&Path>) -> Self {
+        self.state_file = path.map(PathBuf::from);
+        self
+    }
+
+    /// Set a function to call with the delay whenever a request has to wait,
+    /// e.g. to tell the user
+    pub fn on_wait(mut self, callback: fn(Duration)) -> Self {
+        self.on_wait = Some(callback);
+        self
+    }
+
+    /// Wait until the minimum interval since the last request has passed,
+    /// then record the time of a new request
+    pub fn wait(&self) -> Result<(), Error> {
+        sleep(self.reserve()?);
+        Ok(())
+    }
+
+    /// Wait asynchronously until the minimum interval since the last request
+    /// has passed, then record the time of a new request
+    #[cfg(feature = "async")]
+    pub async fn wait_async(&self) -> Result<(), Error> {
+        tokio::time::sleep(self.reserve()?).await;
+        Ok(())
+    }
+
+    /// Record the time of the next request, the minimum interval after the
+    /// last, and get the time to wait until it may be made
+    fn reserve(&self) -> Result<Duration, Error> {
+        // requests are reserved one at a time, so threads take turns
+        let mut last = LAST_REQUEST.lock().unwrap_or_else(|e| e.into_inner());
+        // and so do processes
+        let _lock = self.state_file.as_deref().map(StateLock::acquire).transpose()?;
+
+        let last_request = match (*last, self.read_state()) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+
+        let now = SystemTime::now();
+        let next = last_request.map_or(now, |t| (t + self.interval).max(now));
+        let delay = next.duration_since(now).unwrap_or_default();
+        if let (false, Some(callback)) = (delay.is_zero(), self.on_wait) {
+            callback(delay);
+        }
+
+        *last = Some(next);
+        if let Some(p) = &self.state_file {
+            let millis = next.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+            write(p, millis.to_string())?;
+        }
+
+        Ok(delay)
+    }
+
+    fn read_state(&self) -> Option<SystemTime> {
+        let millis = read_to_string(self.state_file.as_ref()?).ok()?
+            .trim()
+            .parse()
+            .ok()?;
+        Some(UNIX_EPOCH + Duration::from_millis(millis))
+    }
+}
+
+/// An exclusive lock on a state file, held while a lock file exists next to
+/// it, and released when dropped
+struct StateLock {
+    path: PathBuf,
+}
+
+impl StateLock {
+    fn acquire(state_file: &Path) -> Result<Self, Error> {
+        let mut path = OsString::from(state_file);
+        path.push(".lock");
+        let path = PathBuf::from(path);
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self { path }),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    if is_stale(&path) {
+                        let _ = remove_file(&path);
+                    } else {
+                        sleep(Duration::from_millis(10));
+                    }
+                },
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.path);
+    }
+}
+
+fn is_stale(path: &Path) -> bool {
+    metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .is_some_and(|age| age > STALE_LOCK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_throttle_state_file() {
+        let tmp = tempdir().unwrap();
+        let state = tmp.path().join("last_request");
+        let throttle = Throttle::new(Duration::from_millis(200)).state_file(Some(&state));
+
+        // a request recently made by another process
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        write(&state, now.as_millis().to_string()).unwrap();
+
+        let start = Instant::now();
+        throttle.wait().unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(150));
+        assert!(throttle.read_state().unwrap() >= UNIX_EPOCH + now);
+    }
+
+    #[test]
+    fn test_throttle_on_wait() {
+        static WAITED: Mutex<Option<Duration>> = Mutex::new(None);
+        let tmp = tempdir().unwrap();
+        let throttle = Throttle::new(Duration::from_millis(50))
+            .state_file(Some(&tmp.path().join("last_request")))
+            .on_wait(|delay| *WAITED.lock().unwrap() = Some(delay));
+
+        throttle.wait().unwrap();
+        throttle.wait().unwrap();
+        assert!(WAITED.lock().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_throttle_lock() {
+        let tmp = tempdir().unwrap();
+        let state = tmp.path().join("last_request");
+        let lock = tmp.path().join("last_request.lock");
+        let throttle = Throttle::new(Duration::from_millis(0)).state_file(Some(&state));
+
+        // another process is updating the state file
+        write(&lock, "").unwrap();
+        let holder = {
+            let lock = lock.clone();
+            std::thread::spawn(move || {
+                sleep(Duration::from_millis(100));
+                remove_file(lock).unwrap();
+            })
+        };
+        let start = Instant::now();
+        throttle.wait().unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert!(!lock.exists());
+        holder.join().unwrap();
+
+        // a lock left behind is taken over
+        let file = std::fs::File::create(&lock).unwrap();
+        file.set_modified(SystemTime::now() - STALE_LOCK * 2).unwrap();
+        throttle.wait().unwrap();
+        assert!(!lock.exists());
+    }
+}
@@ -0,0 +1,348 @@
+//! Pluggable HTTP transport
+//!
+//! All requests to Advent of Code are made through a [`Transport`], which by
+//! default is [`UreqTransport`]. [`MockTransport`] serves recorded responses
+//! instead, allowing fetching and submitting to be exercised without network
+//! access.
+//!
+//! With the `async` feature, requests made by the async API go through an
+//! [`AsyncTransport`] instead, by default [`ReqwestTransport`].
+use crate::Error;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::read_to_string;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::sync::OnceLock;
+
+/// HTTP request method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+/// An HTTP request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// Form data, sent URL encoded as the body of a POST request
+    pub form: Vec<(String, String)>,
+}
+
+impl Request {
+    /// Get the value of a header, the name is matched case insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Get the path of the request URL, without scheme or host
+    pub fn path(&self) -> &str {
+        let without_scheme = self.url.splitn(2, "://").last().unwrap_or_default();
+        without_scheme.find('/').map_or("/", |i| &without_scheme[i..])
+    }
+}
+
+/// An HTTP response
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        Self { status, body: body.into(), ..Default::default() }
+    }
+
+    /// Add a header to the response
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Get the value of a header, the name is matched case insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Whether the status code indicates success
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// A means of making HTTP requests
+///
+/// Responses with error status codes should be returned as a [`Response`],
+/// errors are reserved for failure to get a response at all.
+pub trait Transport: fmt::Debug + Send + Sync {
+    fn send(&self, request: &Request) -> Result<Response, Error>;
+}
+
+/// Transport making real requests using `ureq`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UreqTransport;
+
+impl Transport for UreqTransport {
+    fn send(&self, request: &Request) -> Result<Response, Error> {
+        let mut req = match request.method {
+            Method::Get => ureq::get(&request.url),
+            Method::Post => ureq::post(&request.url),
+        };
+        for (name, value) in &request.headers {
+            req = req.set(name, value);
+        }
+
+        let result = match request.method {
+            Method::Get => req.call(),
+            Method::Post => {
+                let form: Vec<_> = request.form.iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect();
+                req.send_form(&form)
+            },
+        };
+
+        let resp = match result {
+            Ok(r) | Err(ureq::Error::Status(_, r)) => r,
+            Err(e) => return Err(Error::Network(Box::new(e))),
+        };
+
+        let status = resp.status();
+        let headers = resp.headers_names()
+            .into_iter()
+            .filter_map(|n| {
+                let value = resp.header(&n)?.to_string();
+                Some((n, value))
+            })
+            .collect();
+        let body = resp.into_string()?;
+
+        Ok(Response { status, headers, body })
+    }
+}
+
+/// Future returned by an [`AsyncTransport`]
+#[cfg(feature = "async")]
+pub type ResponseFuture<'a> = Pin<Box<dyn Future<Output = Result<Response, Error>> + Send + 'a>>;
+
+/// A means of making HTTP requests asynchronously
+///
+/// As for [`Transport`], responses with error status codes should be
+/// returned as a [`Response`].
+#[cfg(feature = "async")]
+pub trait AsyncTransport: fmt::Debug + Send + Sync {
+    fn send_async<'a>(&'a self, request: &'a Request) -> ResponseFuture<'a>;
+}
+
+/// Transport making real requests asynchronously using `reqwest`
+#[cfg(feature = "async")]
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "async")]
+impl Default for ReqwestTransport {
+    /// Get a transport sharing a connection pool with all other default
+    /// transports
+    fn default() -> Self {
+        static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+        Self { client: CLIENT.get_or_init(reqwest::Client::new).clone() }
+    }
+}
+
+#[cfg(feature = "async")]
+impl ReqwestTransport {
+    /// Create a transport using the given `reqwest` client
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncTransport for ReqwestTransport {
+    fn send_async<'a>(&'a self, request: &'a Request) -> ResponseFuture<'a> {
+        Box::pin(async move {
+            let mut req = match request.method {
+                Method::Get => self.client.get(&request.url),
+                Method::Post => self.client.post(&request.url).form(&request.form),
+            };
+            for (name, value) in &request.headers {
+                req = req.header(name, value);
+            }
+
+            let network = |e: reqwest::Error| Error::Network(Box::new(e));
+            let resp = req.send().await.map_err(network)?;
+            let status = resp.status().as_u16();
+            let headers = resp.headers()
+                .iter()
+                .filter_map(|(n, v)| Some((n.to_string(), v.to_str().ok()?.to_string())))
+                .collect();
+            let body = resp.text().await.map_err(network)?;
+
+            Ok(Response { status, headers, body })
+        })
+    }
+}
+
+/// Transport serving recorded responses, for use in tests
+///
+/// Responses are matched by the path of the request URL, regardless of the
+/// request method. Requests for paths without a response get a 404.
+/// Conditional requests get a 304 if the `If-None-Match` or
+/// `If-Modified-Since` header matches the response's `ETag` or
+/// `Last-Modified` header. All requests made are recorded, and may be
+/// inspected with [`MockTransport::requests`]. With the `async` feature, it
+/// may also be used as an [`AsyncTransport`].
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    routes: HashMap<String, Response>,
+    requests: Mutex<Vec<Request>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve a response with the given status and body for a path
+    pub fn route(self, path: &str, status: u16, body: impl Into<String>) -> Self {
+        self.response(path, Response::new(status, body))
+    }
+
+    /// Serve a response for a path
+    pub fn response(mut self, path: &str, response: Response) -> Self {
+        self.routes.insert(path.to_string(), response);
+        self
+    }
+
+    /// Serve the files in a directory of fixtures
+    ///
+    /// Each file is served with status 200 at its path relative to the
+    /// directory, with any `.html` extension removed, e.g.
+    /// `2020/day/1.html` is served for `/2020/day/1`, and `2020/day/1/input`
+    /// for `/2020/day/1/input`.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut mock = Self::new();
+        let mut dirs = vec![dir.as_ref().to_path_buf()];
+
+        while let Some(d) = dirs.pop() {
+            for entry in d.read_dir()? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                let relative = path.strip_prefix(dir.as_ref())
+                    .unwrap_or(&path)
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let route = format!("/{}", relative.trim_end_matches(".html"));
+                let body = read_to_string(&path)
+                    .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+                mock = mock.route(&route, 200, body);
+            }
+        }
+
+        Ok(mock)
+    }
+
+    /// Get all requests made so far
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: &Request) -> Result<Response, Error> {
+        self.requests.lock().unwrap().push(request.clone());
+        let response = match self.routes.get(request.path()) {
+            Some(r) => r,
+            None => return Ok(Response::new(404, "404 Not Found")),
+        };
+
+        let matches = |req: &str, resp: &str| match (request.header(req), response.header(resp)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        };
+        if matches("If-None-Match", "ETag") || matches("If-Modified-Since", "Last-Modified") {
+            return Ok(Response { status: 304, body: String::new(), ..response.clone() });
+        }
+
+        Ok(response.clone())
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for std::sync::Arc<T> {
+    fn send(&self, request: &Request) -> Result<Response, Error> {
+        (**self).send(request)
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncTransport for MockTransport {
+    fn send_async<'a>(&'a self, request: &'a Request) -> ResponseFuture<'a> {
+        Box::pin(std::future::ready(self.send(request)))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncTransport + ?Sized> AsyncTransport for std::sync::Arc<T> {
+    fn send_async<'a>(&'a self, request: &'a Request) -> ResponseFuture<'a> {
+        (**self).send_async(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn get(url: &str) -> Request {
+        Request { method: Method::Get, url: url.to_string(), headers: vec![], form: vec![] }
+    }
+
+    #[test]
+    fn test_request_path() {
+        assert_eq!(get("https://adventofcode.com/2020/day/1").path(), "/2020/day/1");
+        assert_eq!(get("http://127.0.0.1:8080/2020").path(), "/2020");
+        assert_eq!(get("https://adventofcode.com").path(), "/");
+    }
+
+    #[test]
+    fn test_mock_from_dir() {
+        let tmp = tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("2020/day/1")).unwrap();
+        fs::write(tmp.path().join("2020/day/1.html"), "brief").unwrap();
+        fs::write(tmp.path().join("2020/day/1/input"), "1 2 3").unwrap();
+
+        let mock = MockTransport::from_dir(tmp.path()).unwrap();
+        let resp = mock.send(&get("https://adventofcode.com/2020/day/1")).unwrap();
+        assert_eq!(resp, Response::new(200, "brief"));
+        let resp = mock.send(&get("https://adventofcode.com/2020/day/1/input")).unwrap();
+        assert_eq!(resp, Response::new(200, "1 2 3"));
+        let resp = mock.send(&get("https://adventofcode.com/2020/day/2")).unwrap();
+        assert_eq!(resp.status, 404);
+        assert_eq!(mock.requests().len(), 3);
+    }
+}